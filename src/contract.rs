@@ -1,23 +1,91 @@
+// Sylvia also generates helpers taking every message field as an argument
+#![allow(clippy::too_many_arguments)]
+
 use crate::{
-    msg::{ConfigResponse, ContributionResponse},
-    storage::{Campaign, CampaignMeta, Link},
+    msg::{
+        AllowlistProof, ConfigResponse, ContributionResponse, Cw721QueryMsg, MessageResponse,
+        OperatorResponse, ReceiptMsg, ReceiveMsg, StakerResponse, TokensResponse, VestedResponse,
+    },
+    storage::{
+        Beneficiary, Campaign, CampaignMeta, CampaignRevision, CampaignStatus, Contribution,
+        Distribution, LedgerEntry, LedgerIndexes, LedgerKind, LegacyCampaign, Link,
+        ModerationAction, ModerationEntry, NftGate, PendingCreator, RefundWindow, Role, Staker,
+        StoredContribution, Tier, Token, Unbonding, VestingSchedule,
+    },
 };
 use cosmwasm_std::{
-    coin, Addr, BankMsg, Binary, Response, StdError, StdResult, SubMsg, Uint128, WasmMsg,
+    coin, from_json, to_json_binary, Addr, Api, BankMsg, Binary, Coin, CosmosMsg, Decimal,
+    Decimal256, DepsMut, Empty, Env, Order, QuerierWrapper, Reply, Response, StdError, StdResult,
+    Storage, SubMsg, Timestamp, Uint128, WasmMsg,
 };
-use cw_storage_plus::{Item, Map};
+use cw_storage_plus::{Bound, IndexedMap, Item, Map, MultiIndex};
+use sha2::{Digest, Sha256};
 use sylvia::{
     contract, entry_points,
-    types::{ExecCtx, InstantiateCtx, QueryCtx},
+    types::{ExecCtx, InstantiateCtx, MigrateCtx, QueryCtx, ReplyCtx},
 };
 
 pub struct KickstarterContract {
     pub(crate) cw20_address: Item<Addr>,
     pub(crate) denom: Item<String>,
+    pub(crate) exchange_rate: Item<Decimal>,
     pub(crate) campaign: Item<Campaign>,
-    pub(crate) contributions: Map<Addr, Uint128>,
+    pub(crate) contributions: Map<Addr, Contribution>,
+    pub(crate) stored_contributions: Map<Addr, StoredContribution>,
+    pub(crate) total_raised: Item<Uint128>,
+    pub(crate) ledger: IndexedMap<u64, LedgerEntry, LedgerIndexes<'static>>,
+    pub(crate) next_ledger_id: Item<u64>,
+    pub(crate) messages: Map<u64, Empty>,
+    pub(crate) distribution: Item<Distribution>,
+    pub(crate) distribution_token: Item<Addr>,
+    pub(crate) token_claims: Map<Addr, Uint128>,
+    pub(crate) stakers: Map<Addr, Staker>,
+    pub(crate) total_staked: Item<Uint128>,
+    pub(crate) revenue_index: Item<Decimal256>,
+    pub(crate) unclaimed_revenue: Item<Uint128>,
+    pub(crate) unbonding_period: Item<u64>,
+    pub(crate) operators: Map<Addr, Vec<Role>>,
+    pub(crate) platform_admin: Item<Addr>,
+    pub(crate) moderation_log: Map<u64, ModerationEntry>,
+    pub(crate) next_moderation_id: Item<u64>,
+    pub(crate) pause_reason: Item<String>,
+    pub(crate) excess_claimed: Item<Uint128>,
+    pub(crate) void_refunded: Item<Uint128>,
+    pub(crate) largest_contribution: Item<Uint128>,
+    pub(crate) payout_fallback: Item<Vec<Coin>>,
+    pub(crate) campaign_history: Map<u32, CampaignRevision>,
+    pub(crate) campaign_version: Item<u32>,
 }
 
+// Version info for migration
+const CONTRACT_NAME: &str = concat!("crates.io:", env!("CARGO_PKG_NAME"));
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// Pagination
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 100;
+
+// Reply to a payout callback, which falls back to a transfer on failure
+const PAYOUT_CALLBACK_REPLY_ID: u64 = 1;
+
+// Campaigns cannot run for longer than a year after they start
+pub const MAX_CAMPAIGN_DURATION: u64 = 365 * 24 * 60 * 60;
+
+// Campaigns can only have their end time extended once
+pub const MAX_DEADLINE_EXTENSIONS: u32 = 1;
+
+// Backers can be refunded for a week after the end time is extended
+pub const REFUND_WINDOW: u64 = 7 * 24 * 60 * 60;
+
+// Maximum length of a backer's memo, in characters
+pub const MAX_MEMO_LENGTH: usize = 280;
+
+// Unstaked receipts are returned after two weeks unless configured otherwise
+pub const DEFAULT_UNBONDING_PERIOD: u64 = 14 * 24 * 60 * 60;
+
+// Staked receipts reach their full voting power after half a year
+pub const VOTING_POWER_MATURITY: u64 = 182 * 24 * 60 * 60;
+
 // Multitest
 pub const FEE_ADDRESS: &str = "cosmwasm1hqxd4t5mxg4m523cl5uk9xtc9fxvdd9qenm8ln9me3she99yvqnqxhpk8e";
 
@@ -27,6 +95,110 @@ pub const FEE_ADDRESS: &str = "cosmwasm1hqxd4t5mxg4m523cl5uk9xtc9fxvdd9qenm8ln9m
 // Mainnet
 // pub const FEE_ADDRESS: &str = "stars1ggyrk0er22cpn8txw7gxyhvq2zn8dw598538jm";
 
+// Initial platform admin moderating campaigns, who can hand the role over
+// with `UpdatePlatformAdmin {}`
+
+// Multitest
+pub const PLATFORM_ADMIN: &str =
+    "cosmwasm16220ensvezzc0ppsn8v9mkq94mh3kzdx8vxmrhf7fhrz5dpurk6sld6xcn";
+
+// Testnet
+// pub const PLATFORM_ADMIN: &str = "stars1ggyrk0er22cpn8txw7gxyhvq2zn8dw598538jm";
+
+// Mainnet
+// pub const PLATFORM_ADMIN: &str = "stars1ggyrk0er22cpn8txw7gxyhvq2zn8dw598538jm";
+
+/// Decodes a hex-encoded SHA-256 hash.
+fn decode_hash(hash: &str) -> StdResult<[u8; 32]> {
+    let mut buf = [0u8; 32];
+    hex::decode_to_slice(hash, &mut buf)
+        .map_err(|_| StdError::generic_err(format!("Invalid hash: {}", hash)))?;
+    Ok(buf)
+}
+
+/// Verifies that `address` is on the allowlist committed to by `merkle_root`
+/// with the given allowance.
+fn verify_allowlist(
+    merkle_root: &str,
+    address: &Addr,
+    allowance: Uint128,
+    proof: &[String],
+) -> StdResult<bool> {
+    let mut hash: [u8; 32] = Sha256::digest(format!("{}{}", address, allowance)).into();
+
+    for sibling in proof {
+        let mut pair = [hash, decode_hash(sibling)?];
+        pair.sort_unstable();
+        hash = Sha256::digest(pair.concat()).into();
+    }
+
+    Ok(hash == decode_hash(merkle_root)?)
+}
+
+/// Parses a semantic version stored by cw2.
+fn parse_version(version: &str) -> StdResult<semver::Version> {
+    semver::Version::parse(version)
+        .map_err(|_| StdError::generic_err(format!("Invalid contract version: {}", version)))
+}
+
+/// Checks whether `owner` holds at least one NFT from the gate's collections.
+fn holds_nft(querier: &QuerierWrapper, gate: &NftGate, owner: &Addr) -> StdResult<bool> {
+    for collection in &gate.collections {
+        let response: TokensResponse = querier.query_wasm_smart(
+            collection,
+            &Cw721QueryMsg::Tokens {
+                owner: owner.to_string(),
+                start_after: None,
+                limit: Some(1),
+            },
+        )?;
+
+        if !response.tokens.is_empty() {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// Builds the message sending `amount` of a native or CW20 token.
+fn send_token(token: &Token, recipient: &Addr, amount: Uint128) -> StdResult<CosmosMsg> {
+    Ok(match token {
+        Token::Native { denom } => BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: vec![coin(amount.u128(), denom)],
+        }
+        .into(),
+        Token::Cw20 { address } => WasmMsg::Execute {
+            contract_addr: address.to_string(),
+            msg: to_json_binary(&cw20::Cw20ExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount,
+            })?,
+            funds: vec![],
+        }
+        .into(),
+    })
+}
+
+/// Builds the message minting `amount` receipt tokens to `recipient`.
+fn mint_receipts(cw20_address: &Addr, recipient: &Addr, amount: Uint128) -> StdResult<WasmMsg> {
+    Ok(WasmMsg::Execute {
+        contract_addr: cw20_address.to_string(),
+        msg: to_json_binary(&cw20::Cw20ExecuteMsg::Mint {
+            recipient: recipient.to_string(),
+            amount,
+        })?,
+        funds: vec![],
+    })
+}
+
+impl Default for KickstarterContract {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[entry_points]
 #[contract]
 impl KickstarterContract {
@@ -34,9 +206,269 @@ impl KickstarterContract {
         Self {
             cw20_address: Item::new("cw20_address"),
             denom: Item::new("denom"),
+            exchange_rate: Item::new("exchange_rate"),
             campaign: Item::new("campaign"),
             contributions: Map::new("contributions"),
+            stored_contributions: Map::new("contributions"),
+            total_raised: Item::new("total_raised"),
+            ledger: IndexedMap::new(
+                "ledger",
+                LedgerIndexes {
+                    address: MultiIndex::new(
+                        |_, entry| entry.address.clone(),
+                        "ledger",
+                        "ledger__address",
+                    ),
+                    timestamp: MultiIndex::new(
+                        |_, entry| entry.timestamp.nanos(),
+                        "ledger",
+                        "ledger__timestamp",
+                    ),
+                },
+            ),
+            next_ledger_id: Item::new("next_ledger_id"),
+            messages: Map::new("messages"),
+            distribution: Item::new("distribution"),
+            distribution_token: Item::new("distribution_token"),
+            token_claims: Map::new("token_claims"),
+            stakers: Map::new("stakers"),
+            total_staked: Item::new("total_staked"),
+            revenue_index: Item::new("revenue_index"),
+            unclaimed_revenue: Item::new("unclaimed_revenue"),
+            unbonding_period: Item::new("unbonding_period"),
+            operators: Map::new("operators"),
+            platform_admin: Item::new("platform_admin"),
+            moderation_log: Map::new("moderation_log"),
+            next_moderation_id: Item::new("next_moderation_id"),
+            pause_reason: Item::new("pause_reason"),
+            excess_claimed: Item::new("excess_claimed"),
+            void_refunded: Item::new("void_refunded"),
+            largest_contribution: Item::new("largest_contribution"),
+            payout_fallback: Item::new("payout_fallback"),
+            campaign_history: Map::new("campaign_history"),
+            campaign_version: Item::new("campaign_version"),
+        }
+    }
+
+    /// Appends an entry to the contribution ledger.
+    fn append_ledger(
+        &self,
+        storage: &mut dyn Storage,
+        env: &Env,
+        address: Addr,
+        kind: LedgerKind,
+        funds: Coin,
+        memo: Option<String>,
+    ) -> StdResult<u64> {
+        let id = self.next_ledger_id.may_load(storage)?.unwrap_or_default();
+        let entry = LedgerEntry {
+            id,
+            address,
+            kind,
+            amount: funds.amount,
+            denom: funds.denom,
+            height: env.block.height,
+            timestamp: env.block.time,
+            memo,
+        };
+
+        self.ledger.save(storage, id, &entry)?;
+        self.next_ledger_id.save(storage, &(id + 1))?;
+
+        Ok(id)
+    }
+
+    /// Checks whether `address` holds `role`. The creator and owners hold
+    /// every role.
+    fn has_role(
+        &self,
+        storage: &dyn Storage,
+        campaign: &Campaign,
+        address: &Addr,
+        role: Role,
+    ) -> StdResult<bool> {
+        if campaign.creator == *address {
+            return Ok(true);
+        }
+
+        let roles = self
+            .operators
+            .may_load(storage, address.clone())?
+            .unwrap_or_default();
+
+        Ok(roles.contains(&Role::Owner) || roles.contains(&role))
+    }
+
+    /// Fails unless `address` holds `role`.
+    fn ensure_role(
+        &self,
+        storage: &dyn Storage,
+        campaign: &Campaign,
+        address: &Addr,
+        role: Role,
+    ) -> StdResult<()> {
+        if !self.has_role(storage, campaign, address, role)? {
+            return Err(StdError::generic_err("Unauthorized"));
+        }
+
+        Ok(())
+    }
+
+    /// Fails while the contract is paused by the platform admin.
+    fn ensure_not_paused(&self, storage: &dyn Storage) -> StdResult<()> {
+        match self.pause_reason.may_load(storage)? {
+            Some(reason) => Err(StdError::generic_err(format!(
+                "Contract is paused: {}",
+                reason
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Records the campaign as a new version in its history.
+    fn record_revision(
+        &self,
+        storage: &mut dyn Storage,
+        env: &Env,
+        editor: Addr,
+        changes: Vec<String>,
+        campaign: &Campaign,
+    ) -> StdResult<u32> {
+        let version = match self.campaign_version.may_load(storage)? {
+            Some(version) => version + 1,
+            None => 0,
+        };
+        let revision = CampaignRevision {
+            version,
+            editor,
+            changes,
+            height: env.block.height,
+            timestamp: env.block.time,
+            campaign: campaign.clone(),
+        };
+
+        self.campaign_history.save(storage, version, &revision)?;
+        self.campaign_version.save(storage, &version)?;
+
+        Ok(version)
+    }
+
+    /// Loads the contribution record of `address`, converting records left
+    /// by the first release.
+    fn load_contribution(
+        &self,
+        storage: &dyn Storage,
+        campaign: &Campaign,
+        address: Addr,
+    ) -> StdResult<Option<Contribution>> {
+        Ok(self
+            .stored_contributions
+            .may_load(storage, address)?
+            .map(|record| record.into_current(campaign)))
+    }
+
+    /// Amount of `token` the contract holds on behalf of backers, the
+    /// creator or stakers, which cannot be recovered.
+    fn liabilities(&self, storage: &dyn Storage, token: &Token) -> StdResult<Uint128> {
+        let mut liabilities = Uint128::zero();
+
+        if let Token::Native { denom } = token {
+            if *denom == self.denom.load(storage)? {
+                let campaign = self.campaign.load(storage)?;
+                let total_raised = self.total_raised.load(storage)?;
+                let excess_claimed = self.excess_claimed.load(storage)?;
+
+                // Raised funds are owed to the creator or backers until the
+                // campaign is finalized, and only the excess afterwards
+                liabilities += if campaign.finalized {
+                    campaign
+                        .excess_of(total_raised, total_raised)
+                        .saturating_sub(excess_claimed)
+                } else {
+                    total_raised
+                        .saturating_sub(excess_claimed)
+                        .saturating_sub(self.void_refunded.load(storage)?)
+                };
+                liabilities += self.unclaimed_revenue.load(storage)?;
+            }
+        }
+
+        if let Some(distribution) = self.distribution.may_load(storage)? {
+            if distribution.token == *token {
+                liabilities += distribution.total - distribution.claimed;
+            }
+        }
+
+        Ok(liabilities)
+    }
+
+    /// Records a moderation action in the audit log.
+    fn moderate(
+        &self,
+        context: ExecCtx,
+        action: ModerationAction,
+        status: CampaignStatus,
+        reason: String,
+    ) -> StdResult<Response> {
+        let mut campaign = self.campaign.load(context.deps.storage)?;
+
+        if self.platform_admin.load(context.deps.storage)? != context.info.sender {
+            return Err(StdError::generic_err("Unauthorized"));
+        }
+
+        if campaign.finalized {
+            return Err(StdError::generic_err("Campaign has already been finalized"));
+        }
+
+        let allowed = match action {
+            ModerationAction::Freeze => campaign.status == CampaignStatus::Active,
+            ModerationAction::Unfreeze => campaign.status == CampaignStatus::Frozen,
+            ModerationAction::Void => campaign.status != CampaignStatus::Voided,
+        };
+        if !allowed {
+            return Err(StdError::generic_err(format!(
+                "Cannot {:?} a {:?} campaign",
+                action, campaign.status
+            )));
+        }
+
+        if reason.is_empty() {
+            return Err(StdError::generic_err("A reason is required"));
         }
+
+        campaign.status = status;
+        self.campaign.save(context.deps.storage, &campaign)?;
+        self.record_revision(
+            context.deps.storage,
+            &context.env,
+            context.info.sender.clone(),
+            vec!["status".to_string()],
+            &campaign,
+        )?;
+
+        let id = self
+            .next_moderation_id
+            .may_load(context.deps.storage)?
+            .unwrap_or_default();
+        let entry = ModerationEntry {
+            id,
+            action,
+            admin: context.info.sender,
+            reason,
+            height: context.env.block.height,
+            timestamp: context.env.block.time,
+        };
+        self.moderation_log.save(context.deps.storage, id, &entry)?;
+        self.next_moderation_id
+            .save(context.deps.storage, &(id + 1))?;
+
+        Ok(Response::default()
+            .add_attribute("action", "moderate")
+            .add_attribute("campaign", campaign.name)
+            .add_attribute("moderation", format!("{:?}", entry.action))
+            .add_attribute("status", format!("{:?}", campaign.status))
+            .add_attribute("admin", entry.admin.to_string())
+            .add_attribute("reason", entry.reason))
     }
 
     #[sv::msg(instantiate)]
@@ -46,34 +478,278 @@ impl KickstarterContract {
         cw20_address: String,
         denom: String,
         campaign: CampaignMeta,
+        exchange_rate: Option<Decimal>,
+        unbonding_period: Option<u64>,
     ) -> StdResult<Response> {
         let cw20_address = context.deps.api.addr_validate(&cw20_address)?;
+        let unbonding_period = unbonding_period.unwrap_or(DEFAULT_UNBONDING_PERIOD);
+
+        let exchange_rate = exchange_rate.unwrap_or(Decimal::one());
+
+        if exchange_rate.is_zero() {
+            return Err(StdError::generic_err(
+                "Exchange rate must be greater than zero",
+            ));
+        }
+
+        let start_time = campaign.start_time.unwrap_or(context.env.block.time);
+
+        if start_time < context.env.block.time {
+            return Err(StdError::generic_err("Start time cannot be in the past"));
+        }
+
+        if campaign.end_time <= start_time {
+            return Err(StdError::generic_err("End time must be after start time"));
+        }
+
+        if campaign.end_time > start_time.plus_seconds(MAX_CAMPAIGN_DURATION) {
+            return Err(StdError::generic_err(
+                "Campaign cannot last longer than a year",
+            ));
+        }
+
+        if let Some(presale) = &campaign.presale {
+            decode_hash(&presale.merkle_root)?;
+
+            if presale.start_time < context.env.block.time || presale.start_time >= start_time {
+                return Err(StdError::generic_err(
+                    "Presale must start between now and the start time",
+                ));
+            }
+        }
+
+        let beneficiaries = campaign
+            .beneficiaries
+            .into_iter()
+            .map(|beneficiary| {
+                Ok(Beneficiary {
+                    address: context.deps.api.addr_validate(&beneficiary.address)?,
+                    share_bps: beneficiary.share_bps,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let dust_recipient = campaign
+            .dust_recipient
+            .map(|address| context.deps.api.addr_validate(&address))
+            .transpose()?;
+
+        // Proceeds can only be routed to a contract, such as a DAO or
+        // multisig treasury
+        let payout_address = campaign
+            .payout_address
+            .map(|address| context.deps.api.addr_validate(&address))
+            .transpose()?;
+        if let Some(address) = &payout_address {
+            context
+                .deps
+                .querier
+                .query_wasm_contract_info(address)
+                .map_err(|_| StdError::generic_err("Payout address must be a contract"))?;
+        }
+
+        let mut nft_gate = campaign.nft_gate;
+        if let Some(gate) = nft_gate.as_mut() {
+            for collection in gate.collections.iter_mut() {
+                *collection = context.deps.api.addr_validate(collection)?.to_string();
+            }
+        }
 
         let campaign = Campaign {
             name: campaign.name,
             description: campaign.description,
+            start_time,
             end_time: campaign.end_time,
             links: campaign.links,
             goal: campaign.goal,
             tiers: campaign.tiers,
             creator: context.info.sender,
+            pending_creator: None,
             minimum_contribution: campaign.minimum_contribution,
+            max_raise: campaign.max_raise,
+            max_contribution_per_address: campaign.max_contribution_per_address,
+            presale: campaign.presale,
+            nft_gate,
+            bonus_schedule: campaign.bonus_schedule,
+            oversubscription: campaign.oversubscription,
+            beneficiaries,
+            dust_recipient,
+            payout_address,
+            payout_callback: campaign.payout_callback,
+            status: CampaignStatus::Active,
+            deadline_extensions: 0,
+            refund_window: None,
+            finalized: false,
         };
 
+        campaign.validate()?;
+
+        cw2::set_contract_version(context.deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
         self.cw20_address
             .save(context.deps.storage, &cw20_address)?;
         self.denom.save(context.deps.storage, &denom)?;
+        self.exchange_rate
+            .save(context.deps.storage, &exchange_rate)?;
         self.campaign.save(context.deps.storage, &campaign)?;
+        self.total_raised
+            .save(context.deps.storage, &Uint128::zero())?;
+        self.total_staked
+            .save(context.deps.storage, &Uint128::zero())?;
+        self.revenue_index
+            .save(context.deps.storage, &Decimal256::zero())?;
+        self.unclaimed_revenue
+            .save(context.deps.storage, &Uint128::zero())?;
+        self.unbonding_period
+            .save(context.deps.storage, &unbonding_period)?;
+        self.excess_claimed
+            .save(context.deps.storage, &Uint128::zero())?;
+        self.void_refunded
+            .save(context.deps.storage, &Uint128::zero())?;
+        self.largest_contribution
+            .save(context.deps.storage, &Uint128::zero())?;
+        self.record_revision(
+            context.deps.storage,
+            &context.env,
+            campaign.creator.clone(),
+            vec![],
+            &campaign,
+        )?;
+        self.platform_admin.save(
+            context.deps.storage,
+            &context.deps.api.addr_validate(PLATFORM_ADMIN)?,
+        )?;
 
         Ok(Response::default()
             .add_attribute("action", "instantiate")
             .add_attribute("cw20_contract", cw20_address.to_string())
             .add_attribute("denom", denom)
+            .add_attribute("exchange_rate", exchange_rate.to_string())
+            .add_attribute("unbonding_period", unbonding_period.to_string())
             .add_attribute("campaign_name", campaign.name)
+            .add_attribute("campaign_start_time", campaign.start_time.to_string())
             .add_attribute("campaign_end_time", campaign.end_time.to_string())
             .add_attribute("campaign_creator", campaign.creator.to_string()))
     }
 
+    #[sv::msg(migrate)]
+    pub fn migrate(&self, context: MigrateCtx) -> StdResult<Response> {
+        let from_version = match cw2::CONTRACT.may_load(context.deps.storage)? {
+            Some(stored) => {
+                if stored.contract != CONTRACT_NAME {
+                    return Err(StdError::generic_err(format!(
+                        "Cannot migrate from {}",
+                        stored.contract
+                    )));
+                }
+
+                let stored_version = parse_version(&stored.version)?;
+                if stored_version > parse_version(CONTRACT_VERSION)? {
+                    return Err(StdError::generic_err(format!(
+                        "Cannot migrate from newer version {}",
+                        stored.version
+                    )));
+                }
+
+                stored.version
+            }
+            // The first release did not store any contract version
+            None => {
+                self.migrate_legacy_storage(
+                    context.deps.storage,
+                    context.deps.api,
+                    &context.deps.querier,
+                    &context.env,
+                )?;
+                "0.1.0".to_string()
+            }
+        };
+
+        cw2::set_contract_version(context.deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+        Ok(Response::default()
+            .add_attribute("action", "migrate")
+            .add_attribute("from_version", from_version)
+            .add_attribute("to_version", CONTRACT_VERSION))
+    }
+
+    /// Converts the storage of the first release, which only kept the
+    /// campaign and a plain amount per contributor, into the current layout.
+    /// Contribution records are left as they are and converted when they are
+    /// next read, so the migration does not depend on the number of backers.
+    fn migrate_legacy_storage(
+        &self,
+        storage: &mut dyn Storage,
+        api: &dyn Api,
+        querier: &QuerierWrapper,
+        env: &Env,
+    ) -> StdResult<()> {
+        let legacy: LegacyCampaign = Item::new("campaign").load(storage)?;
+
+        // Ending a campaign used to clear its contributions, so an ended
+        // campaign without any left has already been paid out
+        let finalized = legacy.end_time < env.block.time && self.contributions.is_empty(storage);
+
+        // The first release had no start time, so the campaign is considered
+        // to have started by the time of the migration
+        let campaign = Campaign {
+            name: legacy.name,
+            description: legacy.description,
+            start_time: env.block.time.min(legacy.end_time),
+            end_time: legacy.end_time,
+            goal: legacy.goal,
+            links: legacy.links,
+            tiers: legacy.tiers,
+            creator: legacy.creator,
+            pending_creator: None,
+            minimum_contribution: legacy.minimum_contribution,
+            max_raise: None,
+            max_contribution_per_address: None,
+            presale: None,
+            nft_gate: None,
+            bonus_schedule: vec![],
+            oversubscription: false,
+            beneficiaries: vec![],
+            dust_recipient: None,
+            payout_address: None,
+            payout_callback: None,
+            status: CampaignStatus::Active,
+            deadline_extensions: 0,
+            refund_window: None,
+            finalized,
+        };
+
+        // The contract only held the contributions, so its balance is their
+        // total. Without iterating over them, the largest contribution is
+        // bounded by that total, which keeps every tier a backer could
+        // qualify for protected.
+        let denom = self.denom.load(storage)?;
+        let total_raised = if finalized {
+            Uint128::zero()
+        } else {
+            querier.query_balance(&env.contract.address, denom)?.amount
+        };
+        let largest_contribution = total_raised;
+
+        self.campaign.save(storage, &campaign)?;
+        self.exchange_rate.save(storage, &Decimal::one())?;
+        self.total_raised.save(storage, &total_raised)?;
+        self.total_staked.save(storage, &Uint128::zero())?;
+        self.revenue_index.save(storage, &Decimal256::zero())?;
+        self.unclaimed_revenue.save(storage, &Uint128::zero())?;
+        self.unbonding_period
+            .save(storage, &DEFAULT_UNBONDING_PERIOD)?;
+        self.excess_claimed.save(storage, &Uint128::zero())?;
+        self.void_refunded.save(storage, &Uint128::zero())?;
+        self.largest_contribution
+            .save(storage, &largest_contribution)?;
+        self.record_revision(storage, env, campaign.creator.clone(), vec![], &campaign)?;
+        self.platform_admin
+            .save(storage, &api.addr_validate(PLATFORM_ADMIN)?)?;
+
+        Ok(())
+    }
+
     #[sv::msg(exec)]
     pub fn update_campaign(
         &self,
@@ -81,31 +757,246 @@ impl KickstarterContract {
         description: String,
         links: Vec<Link>,
         minimum_contribution: Option<Uint128>,
+        name: Option<String>,
+        goal: Option<Uint128>,
+        tiers: Option<Vec<Tier>>,
+        end_time: Option<Timestamp>,
     ) -> StdResult<Response> {
         let mut campaign = self.campaign.load(context.deps.storage)?;
 
-        if campaign.creator != context.info.sender {
-            return Err(StdError::generic_err("Unauthorized"));
+        // Editors can only change the description and links
+        self.ensure_role(
+            context.deps.storage,
+            &campaign,
+            &context.info.sender,
+            Role::Editor,
+        )?;
+        if name.is_some()
+            || goal.is_some()
+            || tiers.is_some()
+            || end_time.is_some()
+            || minimum_contribution.is_some()
+        {
+            self.ensure_role(
+                context.deps.storage,
+                &campaign,
+                &context.info.sender,
+                Role::Owner,
+            )?;
+        }
+
+        if campaign.finalized {
+            return Err(StdError::generic_err("Campaign has already been finalized"));
+        }
+
+        // Backers committed to the goal, tiers and end time they saw, so
+        // those can only change in ways which do not hurt them
+        let has_contributions = !self.contributions.is_empty(context.deps.storage);
+        let mut changes = vec![];
+
+        if let Some(name) = name.filter(|name| *name != campaign.name) {
+            campaign.name = name;
+            changes.push("name".to_string());
+        }
+
+        if description != campaign.description {
+            campaign.description = description;
+            changes.push("description".to_string());
+        }
+
+        if links != campaign.links {
+            campaign.links = links;
+            changes.push("links".to_string());
+        }
+
+        // A zero minimum removes it
+        let minimum_contribution =
+            minimum_contribution.map(|minimum| Some(minimum).filter(|minimum| !minimum.is_zero()));
+        if let Some(minimum_contribution) = minimum_contribution
+            .filter(|minimum_contribution| *minimum_contribution != campaign.minimum_contribution)
+        {
+            campaign.minimum_contribution = minimum_contribution;
+            changes.push("minimum_contribution".to_string());
+        }
+
+        if let Some(goal) = goal.filter(|goal| *goal != campaign.goal) {
+            if has_contributions {
+                return Err(StdError::generic_err(
+                    "Goal cannot be changed once contributions have been made",
+                ));
+            }
+
+            campaign.goal = goal;
+            changes.push("goal".to_string());
+        }
+
+        if let Some(tiers) = tiers.filter(|tiers| *tiers != campaign.tiers) {
+            // New tiers can always be added, but the ones backers already
+            // qualify for must be kept as they are
+            let largest_contribution = self.largest_contribution.load(context.deps.storage)?;
+            campaign.ensure_tiers_kept(&tiers, largest_contribution)?;
+
+            campaign.tiers = tiers;
+            changes.push("tiers".to_string());
+        }
+
+        if let Some(end_time) = end_time.filter(|end_time| *end_time != campaign.end_time) {
+            if has_contributions {
+                return Err(StdError::generic_err(if end_time < campaign.end_time {
+                    "End time cannot be shortened once contributions have been made"
+                } else {
+                    "End time can only be extended with ExtendDeadline"
+                }));
+            }
+
+            if end_time <= context.env.block.time || end_time <= campaign.start_time {
+                return Err(StdError::generic_err(
+                    "End time must be after the start time and in the future",
+                ));
+            }
+
+            if end_time > campaign.start_time.plus_seconds(MAX_CAMPAIGN_DURATION) {
+                return Err(StdError::generic_err(
+                    "Campaign cannot last longer than a year",
+                ));
+            }
+
+            campaign.end_time = end_time;
+            changes.push("end_time".to_string());
+        }
+
+        if changes.is_empty() {
+            return Err(StdError::generic_err("Nothing to update"));
         }
 
-        campaign.description = description;
-        campaign.links = links;
-        campaign.minimum_contribution = minimum_contribution;
+        campaign.validate()?;
 
         self.campaign.save(context.deps.storage, &campaign)?;
+        let version = self.record_revision(
+            context.deps.storage,
+            &context.env,
+            context.info.sender.clone(),
+            changes.clone(),
+            &campaign,
+        )?;
 
         Ok(Response::default()
             .add_attribute("action", "update_campaign")
             .add_attribute("campaign_name", campaign.name)
             .add_attribute("campaign_end_time", campaign.end_time.to_string())
-            .add_attribute("campaign_creator", campaign.creator.to_string()))
+            .add_attribute("campaign_creator", campaign.creator.to_string())
+            .add_attribute("version", version.to_string())
+            .add_attribute("changes", changes.join(",")))
+    }
+
+    #[sv::msg(exec)]
+    pub fn extend_deadline(&self, context: ExecCtx, end_time: Timestamp) -> StdResult<Response> {
+        let mut campaign = self.campaign.load(context.deps.storage)?;
+
+        self.ensure_role(
+            context.deps.storage,
+            &campaign,
+            &context.info.sender,
+            Role::Owner,
+        )?;
+        campaign.ensure_active()?;
+
+        if campaign.end_time < context.env.block.time {
+            return Err(StdError::generic_err("Campaign has ended"));
+        }
+
+        if campaign.deadline_extensions >= MAX_DEADLINE_EXTENSIONS {
+            return Err(StdError::generic_err(format!(
+                "End time cannot be extended more than {} times",
+                MAX_DEADLINE_EXTENSIONS
+            )));
+        }
+
+        if end_time <= campaign.end_time {
+            return Err(StdError::generic_err(
+                "New end time must be after the current one",
+            ));
+        }
+
+        if end_time > campaign.start_time.plus_seconds(MAX_CAMPAIGN_DURATION) {
+            return Err(StdError::generic_err(
+                "Campaign cannot last longer than a year",
+            ));
+        }
+
+        // Existing backers did not sign up for the longer campaign, so they
+        // can be refunded for a while, at the latest until the new end time
+        let previous_end_time = campaign.end_time;
+        let refund_window = RefundWindow {
+            extended_at: context.env.block.time,
+            ends_at: context
+                .env
+                .block
+                .time
+                .plus_seconds(REFUND_WINDOW)
+                .min(end_time),
+        };
+
+        campaign.end_time = end_time;
+        campaign.deadline_extensions += 1;
+        campaign.refund_window = Some(refund_window.clone());
+        self.campaign.save(context.deps.storage, &campaign)?;
+        self.record_revision(
+            context.deps.storage,
+            &context.env,
+            context.info.sender,
+            vec![
+                "end_time".to_string(),
+                "deadline_extensions".to_string(),
+                "refund_window".to_string(),
+            ],
+            &campaign,
+        )?;
+
+        Ok(Response::default()
+            .add_attribute("action", "extend_deadline")
+            .add_attribute("campaign", campaign.name)
+            .add_attribute("previous_end_time", previous_end_time.to_string())
+            .add_attribute("end_time", end_time.to_string())
+            .add_attribute("refund_window_end", refund_window.ends_at.to_string()))
     }
 
     #[sv::msg(exec)]
-    pub fn contribute(&self, context: ExecCtx) -> StdResult<Response> {
+    pub fn contribute(
+        &self,
+        context: ExecCtx,
+        memo: Option<String>,
+        allowlist: Option<AllowlistProof>,
+    ) -> StdResult<Response> {
         let campaign = self.campaign.load(context.deps.storage)?;
         let cw20_address = self.cw20_address.load(context.deps.storage)?;
 
+        campaign.ensure_active()?;
+        self.ensure_not_paused(context.deps.storage)?;
+
+        // During the presale, only allowlisted addresses can contribute
+        let mut presale_allowance = None;
+        if campaign.start_time > context.env.block.time {
+            let presale = match &campaign.presale {
+                Some(presale) if presale.start_time <= context.env.block.time => presale,
+                _ => return Err(StdError::generic_err("Campaign has not started")),
+            };
+
+            let allowlist = allowlist
+                .ok_or_else(|| StdError::generic_err("Address is not on the allowlist"))?;
+
+            if !verify_allowlist(
+                &presale.merkle_root,
+                &context.info.sender,
+                allowlist.allowance,
+                &allowlist.proof,
+            )? {
+                return Err(StdError::generic_err("Invalid allowlist proof"));
+            }
+
+            presale_allowance = Some(allowlist.allowance);
+        }
+
         if campaign.end_time < context.env.block.time {
             return Err(StdError::generic_err("Campaign has ended"));
         }
@@ -114,7 +1005,7 @@ impl KickstarterContract {
             return Err(StdError::generic_err("No funds sent"));
         }
 
-        let contribution = context.info.funds[0].clone();
+        let mut contribution = context.info.funds[0].clone();
 
         if contribution.denom != self.denom.load(context.deps.storage)? {
             return Err(StdError::generic_err("Invalid contribution denom"));
@@ -126,58 +1017,612 @@ impl KickstarterContract {
             }
         }
 
-        let user_contribution = self
-            .contributions
-            .may_load(context.deps.storage, context.info.sender.clone())?;
+        let memo = memo.filter(|memo| !memo.is_empty());
+        if let Some(memo) = &memo {
+            if memo.chars().count() > MAX_MEMO_LENGTH {
+                return Err(StdError::generic_err(format!(
+                    "Memo cannot exceed {} characters",
+                    MAX_MEMO_LENGTH
+                )));
+            }
+        }
 
-        let new_contribution = match user_contribution {
-            Some(contrib) => {
-                self.contributions.update(
-                    context.deps.storage,
-                    context.info.sender.clone(),
-                    |old| match old {
-                        Some(prev) => Ok(prev + contribution.amount),
-                        None => {
-                            return Err(StdError::generic_err(
-                                "Error occurred during contribution update",
-                            ))
-                        }
-                    },
-                )?;
-                contrib + contribution.amount
+        // Only accept what fits under the hard cap, the rest is sent back.
+        // Oversubscribed campaigns accept everything and settle at the end.
+        let total_raised = self.total_raised.load(context.deps.storage)?;
+        let mut excess = Uint128::zero();
+        if let (Some(max_raise), false) = (campaign.max_raise, campaign.oversubscription) {
+            let remaining = max_raise.saturating_sub(total_raised);
+            if remaining.is_zero() {
+                return Err(StdError::generic_err("Campaign has reached its hard cap"));
             }
-            None => {
-                self.contributions.save(
-                    context.deps.storage,
-                    context.info.sender.clone(),
-                    &contribution.amount,
-                )?;
-                contribution.amount
+            if contribution.amount > remaining {
+                excess = contribution.amount - remaining;
+                contribution.amount = remaining;
             }
-        };
+        }
 
-        let cw20_mint_msg = cw20::Cw20ExecuteMsg::Mint {
-            recipient: context.info.sender.to_string(),
-            amount: contribution.amount,
-        };
+        // What is left under the cap must still meet the minimum
+        if let Some(minimum_contribution) = campaign.minimum_contribution {
+            if contribution.amount < minimum_contribution {
+                return Err(StdError::generic_err(
+                    "Contribution below the hard cap is lower than the minimum",
+                ));
+            }
+        }
 
-        let cw20_msg = match serde_json::to_vec(&cw20_mint_msg) {
-            Ok(vec) => Binary::from(vec),
-            Err(e) => return Err(StdError::generic_err(format!("Serialization error: {}", e))),
-        };
+        // During the gated window, only NFT holders can contribute
+        let mut nft_holder = false;
+        if let Some(gate) = &campaign.nft_gate {
+            let gated = gate.end_time > context.env.block.time;
 
-        let mint_cw20 = WasmMsg::Execute {
-            contract_addr: cw20_address.to_string(),
-            msg: cw20_msg,
-            funds: vec![],
-        };
+            if gated || gate.holder_tier.is_some() {
+                nft_holder = holds_nft(&context.deps.querier, gate, &context.info.sender)?;
+            }
 
-        Ok(Response::default()
-            .add_message(mint_cw20)
-            .add_attribute("action", "contribute")
-            .add_attribute("campaign", campaign.name)
-            .add_attribute("contributor", context.info.sender.to_string())
-            .add_attribute("contribution", new_contribution.to_string()))
+            if gated && !nft_holder {
+                return Err(StdError::generic_err(
+                    "Only NFT holders can contribute at this time",
+                ));
+            }
+        }
+
+        // Receipts are minted at the exchange rate, and early backers receive
+        // more receipts for the same contribution. Fractions are rounded down.
+        let exchange_rate = self.exchange_rate.load(context.deps.storage)?;
+        let receipts = contribution.amount.mul_floor(
+            exchange_rate * (Decimal::one() + campaign.bonus_at(context.env.block.time)),
+        );
+
+        if receipts.is_zero() {
+            return Err(StdError::generic_err(
+                "Contribution too low to mint receipts",
+            ));
+        }
+
+        let mut record = match self.load_contribution(
+            context.deps.storage,
+            &campaign,
+            context.info.sender.clone(),
+        )? {
+            Some(mut record) => {
+                record.amount += contribution.amount;
+                record.receipts += receipts;
+                record.last_contribution = context.env.block.time;
+                record.nft_holder |= nft_holder;
+                record
+            }
+            None => Contribution {
+                amount: contribution.amount,
+                receipts,
+                tier: None,
+                first_contribution: context.env.block.time,
+                last_contribution: context.env.block.time,
+                nft_holder,
+                settled: false,
+            },
+        };
+        record.tier = campaign.tier_of(&record, total_raised);
+
+        if let Some(max_contribution) = campaign.max_contribution_per_address {
+            if record.amount > max_contribution {
+                return Err(StdError::generic_err(
+                    "Contribution exceeds the per-address limit",
+                ));
+            }
+        }
+
+        if let Some(allowance) = presale_allowance {
+            if record.amount > allowance {
+                return Err(StdError::generic_err(
+                    "Contribution exceeds the presale allowance",
+                ));
+            }
+        }
+
+        self.contributions
+            .save(context.deps.storage, context.info.sender.clone(), &record)?;
+        self.total_raised
+            .save(context.deps.storage, &(total_raised + contribution.amount))?;
+        self.largest_contribution
+            .update(context.deps.storage, |largest| -> StdResult<_> {
+                Ok(largest.max(record.amount))
+            })?;
+
+        let ledger_id = self.append_ledger(
+            context.deps.storage,
+            &context.env,
+            context.info.sender.clone(),
+            LedgerKind::Contribution,
+            contribution.clone(),
+            memo.clone(),
+        )?;
+
+        if memo.is_some() {
+            self.messages
+                .save(context.deps.storage, ledger_id, &Empty {})?;
+        }
+
+        let mut response = Response::default();
+
+        // Receipts of oversubscribed campaigns are only minted for the
+        // allocated portion, once the campaign has ended
+        if !campaign.oversubscription {
+            response = response.add_message(mint_receipts(
+                &cw20_address,
+                &context.info.sender,
+                receipts,
+            )?);
+        }
+
+        response = response
+            .add_attribute("action", "contribute")
+            .add_attribute("campaign", campaign.name)
+            .add_attribute("contributor", context.info.sender.to_string())
+            .add_attribute("contribution", record.amount.to_string())
+            .add_attribute("receipts", receipts.to_string());
+
+        if let Some(memo) = memo {
+            response = response.add_attribute("memo", memo);
+        }
+
+        if !excess.is_zero() {
+            response = response
+                .add_message(BankMsg::Send {
+                    to_address: context.info.sender.to_string(),
+                    amount: vec![coin(excess.u128(), contribution.denom)],
+                })
+                .add_attribute("refunded_excess", excess.to_string());
+        }
+
+        Ok(response)
+    }
+
+    #[sv::msg(exec)]
+    pub fn update_merkle_root(&self, context: ExecCtx, merkle_root: String) -> StdResult<Response> {
+        let mut campaign = self.campaign.load(context.deps.storage)?;
+
+        self.ensure_role(
+            context.deps.storage,
+            &campaign,
+            &context.info.sender,
+            Role::Owner,
+        )?;
+
+        decode_hash(&merkle_root)?;
+
+        let presale = match campaign.presale.as_mut() {
+            Some(presale) => presale,
+            None => return Err(StdError::generic_err("Campaign has no presale")),
+        };
+
+        if presale.start_time <= context.env.block.time {
+            return Err(StdError::generic_err("Presale has already started"));
+        }
+
+        presale.merkle_root = merkle_root.clone();
+        self.campaign.save(context.deps.storage, &campaign)?;
+        self.record_revision(
+            context.deps.storage,
+            &context.env,
+            context.info.sender,
+            vec!["presale".to_string()],
+            &campaign,
+        )?;
+
+        Ok(Response::default()
+            .add_attribute("action", "update_merkle_root")
+            .add_attribute("campaign", campaign.name)
+            .add_attribute("merkle_root", merkle_root))
+    }
+
+    #[sv::msg(exec)]
+    pub fn propose_new_creator(
+        &self,
+        context: ExecCtx,
+        address: String,
+        expires_at: Option<Timestamp>,
+    ) -> StdResult<Response> {
+        let mut campaign = self.campaign.load(context.deps.storage)?;
+
+        if campaign.creator != context.info.sender {
+            return Err(StdError::generic_err("Unauthorized"));
+        }
+
+        let address = context.deps.api.addr_validate(&address)?;
+
+        if address == campaign.creator {
+            return Err(StdError::generic_err("Address is already the creator"));
+        }
+
+        if let Some(expires_at) = expires_at {
+            if expires_at <= context.env.block.time {
+                return Err(StdError::generic_err("Expiry must be in the future"));
+            }
+        }
+
+        // A new proposal replaces any pending one
+        campaign.pending_creator = Some(PendingCreator {
+            address: address.clone(),
+            expires_at,
+        });
+        self.campaign.save(context.deps.storage, &campaign)?;
+        self.record_revision(
+            context.deps.storage,
+            &context.env,
+            context.info.sender,
+            vec!["pending_creator".to_string()],
+            &campaign,
+        )?;
+
+        let mut response = Response::default()
+            .add_attribute("action", "propose_new_creator")
+            .add_attribute("campaign", campaign.name)
+            .add_attribute("creator", campaign.creator.to_string())
+            .add_attribute("pending_creator", address.to_string());
+
+        if let Some(expires_at) = expires_at {
+            response = response.add_attribute("expires_at", expires_at.to_string());
+        }
+
+        Ok(response)
+    }
+
+    #[sv::msg(exec)]
+    pub fn accept_creator(&self, context: ExecCtx) -> StdResult<Response> {
+        let mut campaign = self.campaign.load(context.deps.storage)?;
+
+        let pending = match &campaign.pending_creator {
+            Some(pending) if pending.address == context.info.sender => pending,
+            _ => return Err(StdError::generic_err("Unauthorized")),
+        };
+
+        if let Some(expires_at) = pending.expires_at {
+            if expires_at <= context.env.block.time {
+                return Err(StdError::generic_err("Creator transfer has expired"));
+            }
+        }
+
+        let previous_creator = campaign.creator;
+        campaign.creator = context.info.sender.clone();
+        campaign.pending_creator = None;
+        self.campaign.save(context.deps.storage, &campaign)?;
+        self.record_revision(
+            context.deps.storage,
+            &context.env,
+            context.info.sender,
+            vec!["creator".to_string(), "pending_creator".to_string()],
+            &campaign,
+        )?;
+
+        Ok(Response::default()
+            .add_attribute("action", "accept_creator")
+            .add_attribute("campaign", campaign.name)
+            .add_attribute("previous_creator", previous_creator.to_string())
+            .add_attribute("creator", campaign.creator.to_string()))
+    }
+
+    #[sv::msg(exec)]
+    pub fn cancel_transfer(&self, context: ExecCtx) -> StdResult<Response> {
+        let mut campaign = self.campaign.load(context.deps.storage)?;
+
+        if campaign.creator != context.info.sender {
+            return Err(StdError::generic_err("Unauthorized"));
+        }
+
+        let pending = campaign
+            .pending_creator
+            .take()
+            .ok_or_else(|| StdError::generic_err("No pending creator transfer"))?;
+        self.campaign.save(context.deps.storage, &campaign)?;
+        self.record_revision(
+            context.deps.storage,
+            &context.env,
+            context.info.sender,
+            vec!["pending_creator".to_string()],
+            &campaign,
+        )?;
+
+        Ok(Response::default()
+            .add_attribute("action", "cancel_transfer")
+            .add_attribute("campaign", campaign.name)
+            .add_attribute("pending_creator", pending.address.to_string()))
+    }
+
+    #[sv::msg(exec)]
+    pub fn grant_role(&self, context: ExecCtx, address: String, role: Role) -> StdResult<Response> {
+        let campaign = self.campaign.load(context.deps.storage)?;
+        let address = context.deps.api.addr_validate(&address)?;
+
+        // Only the creator can appoint other owners
+        if role == Role::Owner && campaign.creator != context.info.sender {
+            return Err(StdError::generic_err("Unauthorized"));
+        }
+
+        self.ensure_role(
+            context.deps.storage,
+            &campaign,
+            &context.info.sender,
+            Role::Owner,
+        )?;
+
+        if address == campaign.creator {
+            return Err(StdError::generic_err("Creator already holds every role"));
+        }
+
+        let mut roles = self
+            .operators
+            .may_load(context.deps.storage, address.clone())?
+            .unwrap_or_default();
+
+        if roles.contains(&role) {
+            return Err(StdError::generic_err("Address already holds this role"));
+        }
+
+        roles.push(role.clone());
+        self.operators
+            .save(context.deps.storage, address.clone(), &roles)?;
+
+        Ok(Response::default()
+            .add_attribute("action", "grant_role")
+            .add_attribute("campaign", campaign.name)
+            .add_attribute("operator", address.to_string())
+            .add_attribute("role", format!("{:?}", role)))
+    }
+
+    #[sv::msg(exec)]
+    pub fn revoke_role(
+        &self,
+        context: ExecCtx,
+        address: String,
+        role: Role,
+    ) -> StdResult<Response> {
+        let campaign = self.campaign.load(context.deps.storage)?;
+        let address = context.deps.api.addr_validate(&address)?;
+
+        // Only the creator can remove other owners
+        if role == Role::Owner && campaign.creator != context.info.sender {
+            return Err(StdError::generic_err("Unauthorized"));
+        }
+
+        self.ensure_role(
+            context.deps.storage,
+            &campaign,
+            &context.info.sender,
+            Role::Owner,
+        )?;
+
+        let mut roles = self
+            .operators
+            .may_load(context.deps.storage, address.clone())?
+            .unwrap_or_default();
+
+        if !roles.contains(&role) {
+            return Err(StdError::generic_err("Address does not hold this role"));
+        }
+
+        roles.retain(|held| *held != role);
+        if roles.is_empty() {
+            self.operators.remove(context.deps.storage, address.clone());
+        } else {
+            self.operators
+                .save(context.deps.storage, address.clone(), &roles)?;
+        }
+
+        Ok(Response::default()
+            .add_attribute("action", "revoke_role")
+            .add_attribute("campaign", campaign.name)
+            .add_attribute("operator", address.to_string())
+            .add_attribute("role", format!("{:?}", role)))
+    }
+
+    #[sv::msg(exec)]
+    pub fn freeze(&self, context: ExecCtx, reason: String) -> StdResult<Response> {
+        self.moderate(
+            context,
+            ModerationAction::Freeze,
+            CampaignStatus::Frozen,
+            reason,
+        )
+    }
+
+    #[sv::msg(exec)]
+    pub fn unfreeze(&self, context: ExecCtx, reason: String) -> StdResult<Response> {
+        self.moderate(
+            context,
+            ModerationAction::Unfreeze,
+            CampaignStatus::Active,
+            reason,
+        )
+    }
+
+    #[sv::msg(exec)]
+    pub fn void(&self, context: ExecCtx, reason: String) -> StdResult<Response> {
+        self.moderate(
+            context,
+            ModerationAction::Void,
+            CampaignStatus::Voided,
+            reason,
+        )
+    }
+
+    #[sv::msg(exec)]
+    pub fn update_platform_admin(&self, context: ExecCtx, address: String) -> StdResult<Response> {
+        if self.platform_admin.load(context.deps.storage)? != context.info.sender {
+            return Err(StdError::generic_err("Unauthorized"));
+        }
+
+        let address = context.deps.api.addr_validate(&address)?;
+        self.platform_admin.save(context.deps.storage, &address)?;
+
+        Ok(Response::default()
+            .add_attribute("action", "update_platform_admin")
+            .add_attribute("previous_admin", context.info.sender.to_string())
+            .add_attribute("admin", address.to_string()))
+    }
+
+    #[sv::msg(exec)]
+    pub fn pause(&self, context: ExecCtx, reason: String) -> StdResult<Response> {
+        if self.platform_admin.load(context.deps.storage)? != context.info.sender {
+            return Err(StdError::generic_err("Unauthorized"));
+        }
+
+        if reason.is_empty() {
+            return Err(StdError::generic_err("A reason is required"));
+        }
+
+        // Pausing again only updates the reason
+        self.pause_reason.save(context.deps.storage, &reason)?;
+
+        Ok(Response::default()
+            .add_attribute("action", "pause")
+            .add_attribute("admin", context.info.sender.to_string())
+            .add_attribute("reason", reason))
+    }
+
+    #[sv::msg(exec)]
+    pub fn unpause(&self, context: ExecCtx) -> StdResult<Response> {
+        if self.platform_admin.load(context.deps.storage)? != context.info.sender {
+            return Err(StdError::generic_err("Unauthorized"));
+        }
+
+        if self.pause_reason.may_load(context.deps.storage)?.is_none() {
+            return Err(StdError::generic_err("Contract is not paused"));
+        }
+
+        self.pause_reason.remove(context.deps.storage);
+
+        Ok(Response::default()
+            .add_attribute("action", "unpause")
+            .add_attribute("admin", context.info.sender.to_string()))
+    }
+
+    #[sv::msg(exec)]
+    pub fn recover_tokens(
+        &self,
+        context: ExecCtx,
+        token: Token,
+        recipient: String,
+    ) -> StdResult<Response> {
+        if self.platform_admin.load(context.deps.storage)? != context.info.sender {
+            return Err(StdError::generic_err("Unauthorized"));
+        }
+
+        let recipient = context.deps.api.addr_validate(&recipient)?;
+        let contract_address = &context.env.contract.address;
+
+        let balance = match &token {
+            Token::Native { denom } => {
+                context
+                    .deps
+                    .querier
+                    .query_balance(contract_address, denom)?
+                    .amount
+            }
+            Token::Cw20 { address } => {
+                // Receipts are minted to backers and staked in the contract
+                if *address == self.cw20_address.load(context.deps.storage)? {
+                    return Err(StdError::generic_err("Receipt tokens cannot be recovered"));
+                }
+
+                let response: cw20::BalanceResponse = context.deps.querier.query_wasm_smart(
+                    address,
+                    &cw20::Cw20QueryMsg::Balance {
+                        address: contract_address.to_string(),
+                    },
+                )?;
+                response.balance
+            }
+        };
+
+        let liabilities = self.liabilities(context.deps.storage, &token)?;
+        let amount = balance.saturating_sub(liabilities);
+
+        if amount.is_zero() {
+            return Err(StdError::generic_err("No tokens to recover"));
+        }
+
+        Ok(Response::default()
+            .add_message(send_token(&token, &recipient, amount)?)
+            .add_attribute("action", "recover_tokens")
+            .add_attribute("admin", context.info.sender.to_string())
+            .add_attribute("token", token.to_string())
+            .add_attribute("amount", amount.to_string())
+            .add_attribute("liabilities", liabilities.to_string())
+            .add_attribute("recipient", recipient.to_string()))
+    }
+
+    #[sv::msg(exec)]
+    pub fn claim_refund(&self, context: ExecCtx) -> StdResult<Response> {
+        let campaign = self.campaign.load(context.deps.storage)?;
+        let voided = campaign.status == CampaignStatus::Voided;
+
+        let record = self
+            .load_contribution(context.deps.storage, &campaign, context.info.sender.clone())?
+            .ok_or_else(|| StdError::generic_err("No contribution found"))?;
+
+        // After a deadline extension, earlier backers of oversubscribed
+        // campaigns can also be refunded
+        if !voided {
+            let in_window = campaign.refund_window.as_ref().is_some_and(|window| {
+                window.covers(record.first_contribution, context.env.block.time)
+            });
+
+            if !in_window {
+                return Err(StdError::generic_err("Refunds are not available"));
+            }
+        }
+
+        // Other backers hold receipts, even once the campaign is voided, and
+        // are refunded by returning them so they are burnt
+        if !campaign.oversubscription {
+            return Err(StdError::generic_err("Return your receipts to be refunded"));
+        }
+
+        // Backers of oversubscribed campaigns are refunded whatever is left of
+        // their contribution. The raised total of voided campaigns is left
+        // untouched so settled allocations keep their excess.
+        let total_raised = self.total_raised.load(context.deps.storage)?;
+        let refund = if record.settled {
+            record.amount - campaign.excess_of(record.amount, total_raised)
+        } else {
+            record.amount
+        };
+
+        self.contributions
+            .remove(context.deps.storage, context.info.sender.clone());
+        if voided {
+            self.void_refunded
+                .update(context.deps.storage, |refunded| -> StdResult<_> {
+                    Ok(refunded + refund)
+                })?;
+        } else {
+            self.total_raised
+                .update(context.deps.storage, |total_raised| {
+                    total_raised.checked_sub(refund).map_err(StdError::overflow)
+                })?;
+        }
+
+        let denom = self.denom.load(context.deps.storage)?;
+
+        self.append_ledger(
+            context.deps.storage,
+            &context.env,
+            context.info.sender.clone(),
+            LedgerKind::Refund,
+            coin(refund.u128(), denom.clone()),
+            None,
+        )?;
+
+        Ok(Response::default()
+            .add_message(BankMsg::Send {
+                to_address: context.info.sender.to_string(),
+                amount: vec![coin(refund.u128(), denom)],
+            })
+            .add_attribute("action", "claim_refund")
+            .add_attribute("campaign", campaign.name)
+            .add_attribute("contributor", context.info.sender.to_string())
+            .add_attribute("refund", refund.to_string()))
     }
 
     #[sv::msg(exec)]
@@ -186,87 +1631,496 @@ impl KickstarterContract {
         context: InstantiateCtx,
         sender: String,
         amount: Uint128,
+        msg: Binary,
     ) -> StdResult<Response> {
         let sender = context.deps.api.addr_validate(&sender)?;
+        let cw20_address = self.cw20_address.load(context.deps.storage)?;
+
+        // Receipt tokens are sent back for refunds or staking, any other CW20
+        // token is a project token deposit
+        if context.info.sender == cw20_address {
+            // An empty hook message refunds, as it did before staking existed
+            let hook = if msg.is_empty() || msg.as_slice() == b"{}" {
+                ReceiptMsg::Refund {}
+            } else {
+                from_json(&msg)?
+            };
+
+            return match hook {
+                ReceiptMsg::Refund {} => self.refund(context.deps, &context.env, sender, amount),
+                ReceiptMsg::Stake {} => self.stake(context.deps, &context.env, sender, amount),
+            };
+        }
+
+        // Anyone can call this directly with a forged sender, so only the CW20
+        // contract declared by the creator is trusted
+        if self.distribution_token.may_load(context.deps.storage)?
+            != Some(context.info.sender.clone())
+        {
+            return Err(StdError::generic_err(
+                "Token has not been allowed for distribution",
+            ));
+        }
+
+        match from_json(&msg)? {
+            ReceiveMsg::DepositTokens {
+                claim_deadline,
+                vesting,
+            } => self.deposit(
+                context.deps,
+                &context.env,
+                sender,
+                Token::Cw20 {
+                    address: context.info.sender,
+                },
+                amount,
+                claim_deadline,
+                vesting,
+            ),
+        }
+    }
+
+    /// Burns returned receipts and refunds the matching contribution.
+    fn refund(
+        &self,
+        deps: DepsMut,
+        env: &Env,
+        sender: Addr,
+        amount: Uint128,
+    ) -> StdResult<Response> {
+        let campaign = self.campaign.load(deps.storage)?;
+
+        if campaign.finalized {
+            return Err(StdError::generic_err("Campaign has been finalized"));
+        }
+
+        // Allocations are settled pro-rata through `claim_excess` instead
+        if campaign.oversubscription {
+            return Err(StdError::generic_err(
+                "Oversubscribed campaigns cannot be refunded",
+            ));
+        }
+
+        let record = self.load_contribution(deps.storage, &campaign, sender.clone())?;
+
+        let mut record = match record {
+            Some(record) => record,
+            None => return Err(StdError::generic_err("No contribution found")),
+        };
+        let contribution = record.amount;
+
+        if amount > record.receipts {
+            return Err(StdError::generic_err(
+                "Amount sent is greater than contribution",
+            ));
+        }
+
+        // Receipts may have been minted at a different rate or with a bonus,
+        // so only the matching share of the underlying contribution is
+        // refunded. Returning every receipt always refunds the full amount.
+        let refund = if amount == record.receipts {
+            record.amount
+        } else {
+            record.amount.multiply_ratio(amount, record.receipts)
+        };
+
+        if refund.is_zero() {
+            return Err(StdError::generic_err("Amount sent is too low to refund"));
+        }
+
+        let cw20_address = self.cw20_address.load(deps.storage)?;
+
+        let cw20_burn_msg = cw20::Cw20ExecuteMsg::Burn { amount };
+
+        let cw20_msg = match serde_json::to_vec(&cw20_burn_msg) {
+            Ok(vec) => Binary::from(vec),
+            Err(e) => return Err(StdError::generic_err(format!("Serialization error: {}", e))),
+        };
+
+        let burn_cw20 = WasmMsg::Execute {
+            contract_addr: cw20_address.to_string(),
+            msg: cw20_msg,
+            funds: vec![],
+        };
+
+        if amount < record.receipts {
+            record.amount = contribution - refund;
+            record.receipts -= amount;
+            record.tier = campaign.tier_of(&record, self.total_raised.load(deps.storage)?);
+            self.contributions
+                .save(deps.storage, sender.clone(), &record)?;
+        } else {
+            self.contributions.remove(deps.storage, sender.clone());
+        }
+
+        self.total_raised.update(deps.storage, |total_raised| {
+            total_raised.checked_sub(refund).map_err(StdError::overflow)
+        })?;
+
+        let denom = self.denom.load(deps.storage)?;
+
+        self.append_ledger(
+            deps.storage,
+            env,
+            sender.clone(),
+            LedgerKind::Refund,
+            coin(refund.u128(), denom.clone()),
+            None,
+        )?;
+
+        // Send tokens back to user
+        let msg = BankMsg::Send {
+            to_address: sender.to_string(),
+            amount: vec![coin(refund.u128(), denom)],
+        };
+
+        let send_msg = SubMsg::new(msg);
+
+        Ok(Response::default()
+            .add_submessage(send_msg)
+            .add_message(burn_cw20)
+            .add_attribute("action", "refund")
+            .add_attribute("contributor", sender.to_string())
+            .add_attribute("contribution", contribution.to_string())
+            .add_attribute("refund", refund.to_string()))
+    }
+
+    /// Adds project tokens to the distribution claimable by backers.
+    fn deposit(
+        &self,
+        deps: DepsMut,
+        env: &Env,
+        depositor: Addr,
+        token: Token,
+        amount: Uint128,
+        claim_deadline: Option<Timestamp>,
+        vesting: Option<VestingSchedule>,
+    ) -> StdResult<Response> {
+        let campaign = self.campaign.load(deps.storage)?;
+
+        self.ensure_role(deps.storage, &campaign, &depositor, Role::Finance)?;
+
+        if !campaign.finalized {
+            return Err(StdError::generic_err("Campaign has not been finalized"));
+        }
+
+        if amount.is_zero() {
+            return Err(StdError::generic_err("No funds sent"));
+        }
+
+        if let Token::Native { denom } = &token {
+            if denom == &self.denom.load(deps.storage)? {
+                return Err(StdError::generic_err(
+                    "Cannot distribute the campaign denom",
+                ));
+            }
+        }
+
+        let distribution = match self.distribution.may_load(deps.storage)? {
+            Some(mut distribution) => {
+                if distribution.token != token {
+                    return Err(StdError::generic_err(
+                        "Tokens already being distributed are different",
+                    ));
+                }
+
+                if distribution.claim_deadline <= env.block.time {
+                    return Err(StdError::generic_err("Claim deadline has passed"));
+                }
+
+                if vesting.is_some() && vesting != distribution.vesting {
+                    return Err(StdError::generic_err("Vesting schedule cannot be changed"));
+                }
+
+                if let Some(claim_deadline) = claim_deadline {
+                    if claim_deadline < distribution.claim_deadline {
+                        return Err(StdError::generic_err(
+                            "Claim deadline cannot be brought forward",
+                        ));
+                    }
+                    distribution.claim_deadline = claim_deadline;
+                }
+
+                distribution.total += amount;
+                distribution
+            }
+            None => {
+                let claim_deadline = claim_deadline
+                    .ok_or_else(|| StdError::generic_err("Claim deadline is required"))?;
+
+                if claim_deadline <= env.block.time {
+                    return Err(StdError::generic_err(
+                        "Claim deadline must be in the future",
+                    ));
+                }
+
+                if let Some(vesting) = &vesting {
+                    if vesting.duration == 0 || vesting.cliff > vesting.duration {
+                        return Err(StdError::generic_err(
+                            "Vesting cliff cannot be longer than its duration",
+                        ));
+                    }
+
+                    if vesting.start_time.plus_seconds(vesting.duration) > claim_deadline {
+                        return Err(StdError::generic_err(
+                            "Claim deadline cannot be before the end of vesting",
+                        ));
+                    }
+                }
+
+                let total_contributions = self.total_raised.load(deps.storage)?;
+
+                if total_contributions.is_zero() {
+                    return Err(StdError::generic_err("Campaign has no contributions"));
+                }
+
+                Distribution {
+                    token,
+                    total: amount,
+                    claimed: Uint128::zero(),
+                    claim_deadline,
+                    total_contributions,
+                    vesting,
+                }
+            }
+        };
+
+        self.distribution.save(deps.storage, &distribution)?;
+
+        Ok(Response::default()
+            .add_attribute("action", "deposit_tokens")
+            .add_attribute("campaign", campaign.name)
+            .add_attribute("token", distribution.token.to_string())
+            .add_attribute("amount", amount.to_string())
+            .add_attribute("claim_deadline", distribution.claim_deadline.to_string()))
+    }
+
+    /// Returns the share of distributed tokens allocated to `address` and how
+    /// much of it is vested at `time`.
+    fn vested_tokens_for(
+        &self,
+        storage: &dyn Storage,
+        distribution: &Distribution,
+        address: &Addr,
+        time: Timestamp,
+    ) -> StdResult<VestedResponse> {
+        let campaign = self.campaign.load(storage)?;
+        let contribution = self
+            .load_contribution(storage, &campaign, address.clone())?
+            .map(|record| record.amount)
+            .unwrap_or_default();
+        let claimed = self
+            .token_claims
+            .may_load(storage, address.clone())?
+            .unwrap_or_default();
+
+        let total = distribution
+            .total
+            .multiply_ratio(contribution, distribution.total_contributions);
+        let vested = match &distribution.vesting {
+            Some(vesting) => vesting.vested(total, time),
+            None => total,
+        };
+
+        Ok(VestedResponse {
+            total,
+            vested,
+            claimed,
+            claimable: vested.saturating_sub(claimed),
+            locked: total - vested,
+        })
+    }
+
+    #[sv::msg(exec)]
+    pub fn allow_distribution_token(
+        &self,
+        context: ExecCtx,
+        address: String,
+    ) -> StdResult<Response> {
+        let campaign = self.campaign.load(context.deps.storage)?;
+
+        self.ensure_role(
+            context.deps.storage,
+            &campaign,
+            &context.info.sender,
+            Role::Finance,
+        )?;
+
+        if !campaign.finalized {
+            return Err(StdError::generic_err("Campaign has not been finalized"));
+        }
+
+        if self.distribution.may_load(context.deps.storage)?.is_some() {
+            return Err(StdError::generic_err(
+                "Tokens are already being distributed",
+            ));
+        }
+
+        let address = context.deps.api.addr_validate(&address)?;
+
+        if address == self.cw20_address.load(context.deps.storage)? {
+            return Err(StdError::generic_err("Cannot distribute the receipt token"));
+        }
+
+        self.distribution_token
+            .save(context.deps.storage, &address)?;
+
+        Ok(Response::default()
+            .add_attribute("action", "allow_distribution_token")
+            .add_attribute("token", address.to_string()))
+    }
+
+    #[sv::msg(exec)]
+    pub fn deposit_tokens(
+        &self,
+        context: ExecCtx,
+        claim_deadline: Option<Timestamp>,
+        vesting: Option<VestingSchedule>,
+    ) -> StdResult<Response> {
+        if context.info.funds.len() != 1 {
+            return Err(StdError::generic_err("Send exactly one native token"));
+        }
 
-        let contribution = self
-            .contributions
-            .may_load(context.deps.storage, sender.clone())?;
+        let funds = context.info.funds[0].clone();
 
-        let contribution = match contribution {
-            Some(contribution) => contribution,
-            None => return Err(StdError::generic_err("No contribution found")),
-        };
+        self.deposit(
+            context.deps,
+            &context.env,
+            context.info.sender,
+            Token::Native { denom: funds.denom },
+            funds.amount,
+            claim_deadline,
+            vesting,
+        )
+    }
 
-        if amount > contribution {
-            return Err(StdError::generic_err(
-                "Amount sent is greater than contribution",
-            ));
+    #[sv::msg(exec)]
+    pub fn claim_tokens(&self, context: ExecCtx) -> StdResult<Response> {
+        let mut distribution = self
+            .distribution
+            .may_load(context.deps.storage)?
+            .ok_or_else(|| StdError::generic_err("No tokens to claim"))?;
+
+        if distribution.claim_deadline <= context.env.block.time {
+            return Err(StdError::generic_err("Claim deadline has passed"));
         }
 
-        let cw20_address = self.cw20_address.load(context.deps.storage)?;
+        let claimable = self
+            .vested_tokens_for(
+                context.deps.storage,
+                &distribution,
+                &context.info.sender,
+                context.env.block.time,
+            )?
+            .claimable;
 
-        let cw20_burn_msg = cw20::Cw20ExecuteMsg::Burn { amount };
+        if claimable.is_zero() {
+            return Err(StdError::generic_err("No tokens to claim"));
+        }
 
-        let cw20_msg = match serde_json::to_vec(&cw20_burn_msg) {
-            Ok(vec) => Binary::from(vec),
-            Err(e) => return Err(StdError::generic_err(format!("Serialization error: {}", e))),
-        };
+        self.token_claims.update(
+            context.deps.storage,
+            context.info.sender.clone(),
+            |claimed| -> StdResult<_> { Ok(claimed.unwrap_or_default() + claimable) },
+        )?;
 
-        let burn_cw20 = WasmMsg::Execute {
-            contract_addr: cw20_address.to_string(),
-            msg: cw20_msg,
-            funds: vec![],
-        };
+        distribution.claimed += claimable;
+        self.distribution
+            .save(context.deps.storage, &distribution)?;
 
-        if amount < contribution {
-            self.contributions
-                .update(context.deps.storage, sender.clone(), |old| match old {
-                    Some(prev) => Ok(prev - amount),
-                    None => Err(StdError::generic_err(
-                        "Error occurred during contribution update",
-                    )),
-                })?;
-        } else {
-            self.contributions
-                .remove(context.deps.storage, sender.clone());
+        Ok(Response::default()
+            .add_message(send_token(
+                &distribution.token,
+                &context.info.sender,
+                claimable,
+            )?)
+            .add_attribute("action", "claim_tokens")
+            .add_attribute("claimer", context.info.sender.to_string())
+            .add_attribute("token", distribution.token.to_string())
+            .add_attribute("amount", claimable.to_string()))
+    }
+
+    #[sv::msg(exec)]
+    pub fn withdraw_unclaimed_tokens(&self, context: ExecCtx) -> StdResult<Response> {
+        let campaign = self.campaign.load(context.deps.storage)?;
+
+        self.ensure_role(
+            context.deps.storage,
+            &campaign,
+            &context.info.sender,
+            Role::Finance,
+        )?;
+
+        let mut distribution = self
+            .distribution
+            .may_load(context.deps.storage)?
+            .ok_or_else(|| StdError::generic_err("No tokens to withdraw"))?;
+
+        if distribution.claim_deadline > context.env.block.time {
+            return Err(StdError::generic_err("Claim deadline has not passed"));
         }
 
-        // Send tokens back to user
-        let msg = BankMsg::Send {
-            to_address: sender.to_string(),
-            amount: vec![coin(amount.u128(), self.denom.load(context.deps.storage)?)],
-        };
+        let unclaimed = distribution.total - distribution.claimed;
 
-        let send_msg = SubMsg::new(msg);
+        if unclaimed.is_zero() {
+            return Err(StdError::generic_err("No tokens to withdraw"));
+        }
+
+        distribution.claimed = distribution.total;
+        self.distribution
+            .save(context.deps.storage, &distribution)?;
 
         Ok(Response::default()
-            .add_submessage(send_msg)
-            .add_message(burn_cw20)
-            .add_attribute("action", "refund")
-            .add_attribute("contributor", context.info.sender.to_string())
-            .add_attribute("contribution", contribution.to_string()))
+            .add_message(send_token(
+                &distribution.token,
+                &campaign.creator,
+                unclaimed,
+            )?)
+            .add_attribute("action", "withdraw_unclaimed_tokens")
+            .add_attribute("campaign", campaign.name)
+            .add_attribute("token", distribution.token.to_string())
+            .add_attribute("amount", unclaimed.to_string()))
     }
 
     #[sv::msg(exec)]
     pub fn end_campaign(&self, context: InstantiateCtx) -> StdResult<Response> {
-        let campaign = self.campaign.load(context.deps.storage)?;
+        let mut campaign = self.campaign.load(context.deps.storage)?;
         let denom = self.denom.load(context.deps.storage)?;
 
-        if campaign.creator != context.info.sender {
-            return Err(StdError::generic_err("Unauthorized"));
-        }
+        self.ensure_role(
+            context.deps.storage,
+            &campaign,
+            &context.info.sender,
+            Role::Finance,
+        )?;
 
         if campaign.end_time >= context.env.block.time {
             return Err(StdError::generic_err("Campaign has not ended"));
         }
 
+        if campaign.finalized {
+            return Err(StdError::generic_err("Campaign has already been finalized"));
+        }
+
+        campaign.ensure_active()?;
+        self.ensure_not_paused(context.deps.storage)?;
+
         let contract_address = context.env.contract.address.to_string();
-        let contract_balance = context
+        let mut contract_balance = context
             .deps
             .querier
-            .query_balance(&contract_address, denom.clone())
-            .map_err(|error| error)?;
+            .query_balance(&contract_address, denom.clone())?;
+
+        // Contributions over the cap stay in the contract for `claim_excess`,
+        // except for the excess backers already claimed since the end time
+        if campaign.oversubscription {
+            let total_raised = self.total_raised.load(context.deps.storage)?;
+            let excess_claimed = self.excess_claimed.load(context.deps.storage)?;
+            let unclaimed_excess = campaign
+                .excess_of(total_raised, total_raised)
+                .saturating_sub(excess_claimed);
+            contract_balance.amount = contract_balance.amount.saturating_sub(unclaimed_excess);
+        }
 
         let fee_amount = contract_balance.amount.u128() / 20;
         let fee_msg = BankMsg::Send {
@@ -275,25 +2129,353 @@ impl KickstarterContract {
         };
         let fee_send_msg = SubMsg::new(fee_msg);
 
-        let msg = BankMsg::Send {
-            to_address: context.info.sender.to_string(),
-            amount: vec![coin(
-                contract_balance.amount.u128() - fee_amount,
-                denom.clone(),
-            )],
-        };
-        let send_msg = SubMsg::new(msg);
+        // The payout is split between the beneficiaries, if any
+        let payout = contract_balance.amount - Uint128::new(fee_amount);
+        let storage = context.deps.storage;
+        let send_msgs = campaign
+            .split_payout(payout)
+            .into_iter()
+            .map(|(address, amount)| {
+                let funds = vec![coin(amount.u128(), denom.clone())];
+                match &campaign.payout_callback {
+                    // The treasury is notified of the deposit through the
+                    // callback, which carries the payout. Should the treasury
+                    // reject it, the payout is sent as a plain transfer so the
+                    // campaign can still be finalized.
+                    Some(callback) => {
+                        self.payout_fallback.save(storage, &funds)?;
 
-        self.contributions.clear(context.deps.storage);
+                        Ok(SubMsg::reply_always(
+                            WasmMsg::Execute {
+                                contract_addr: address.to_string(),
+                                msg: callback.clone(),
+                                funds,
+                            },
+                            PAYOUT_CALLBACK_REPLY_ID,
+                        ))
+                    }
+                    None => Ok(SubMsg::new(BankMsg::Send {
+                        to_address: address.to_string(),
+                        amount: funds,
+                    })),
+                }
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        // Contribution records are kept as-is; flagging the campaign as
+        // finalized keeps this O(1) regardless of the number of backers.
+        campaign.finalized = true;
+        self.campaign.save(storage, &campaign)?;
+        self.record_revision(
+            storage,
+            &context.env,
+            context.info.sender,
+            vec!["finalized".to_string()],
+            &campaign,
+        )?;
 
         Ok(Response::default()
-            .add_submessage(send_msg)
+            .add_submessages(send_msgs)
             .add_submessage(fee_send_msg)
             .add_attribute("action", "end_campaign")
             .add_attribute("campaign", campaign.name)
             .add_attribute("total_contributions", contract_balance.amount.to_string()))
     }
 
+    #[sv::msg(reply)]
+    pub fn reply(&self, context: ReplyCtx, reply: Reply) -> StdResult<Response> {
+        match reply.id {
+            PAYOUT_CALLBACK_REPLY_ID => {
+                let funds = self.payout_fallback.load(context.deps.storage)?;
+                self.payout_fallback.remove(context.deps.storage);
+
+                // The treasury accepted the callback along with the payout
+                let error = match reply.result.into_result() {
+                    Ok(_) => return Ok(Response::default()),
+                    Err(error) => error,
+                };
+
+                let campaign = self.campaign.load(context.deps.storage)?;
+                let payout_address = campaign
+                    .payout_address
+                    .ok_or_else(|| StdError::generic_err("No payout address"))?;
+
+                Ok(Response::default()
+                    .add_message(BankMsg::Send {
+                        to_address: payout_address.to_string(),
+                        amount: funds,
+                    })
+                    .add_attribute("action", "payout_callback_failed")
+                    .add_attribute("error", error))
+            }
+            id => Err(StdError::generic_err(format!("Unknown reply id: {}", id))),
+        }
+    }
+
+    #[sv::msg(exec)]
+    pub fn claim_excess(&self, context: ExecCtx) -> StdResult<Response> {
+        let campaign = self.campaign.load(context.deps.storage)?;
+
+        if !campaign.oversubscription {
+            return Err(StdError::generic_err("Campaign is not oversubscribable"));
+        }
+
+        // Backers of voided campaigns are refunded in full through
+        // `claim_refund` instead
+        if campaign.status == CampaignStatus::Voided {
+            return Err(StdError::generic_err("Campaign has been voided"));
+        }
+
+        if campaign.end_time >= context.env.block.time {
+            return Err(StdError::generic_err("Campaign has not ended"));
+        }
+
+        let mut record = self
+            .load_contribution(context.deps.storage, &campaign, context.info.sender.clone())?
+            .ok_or_else(|| StdError::generic_err("No contribution found"))?;
+
+        if record.settled {
+            return Err(StdError::generic_err(
+                "Contribution has already been settled",
+            ));
+        }
+
+        let total_raised = self.total_raised.load(context.deps.storage)?;
+        let excess = campaign.excess_of(record.amount, total_raised);
+        let allocation = record.amount - excess;
+
+        // Receipts are only minted for the allocated portion. The recorded
+        // amount is kept as contributed, so token distributions stay
+        // proportional to the allocations.
+        record.receipts = record.receipts.multiply_ratio(allocation, record.amount);
+        record.settled = true;
+        record.tier = campaign.tier_of(&record, total_raised);
+
+        self.contributions
+            .save(context.deps.storage, context.info.sender.clone(), &record)?;
+        self.excess_claimed
+            .update(context.deps.storage, |claimed| -> StdResult<_> {
+                Ok(claimed + excess)
+            })?;
+
+        let mut response = Response::default()
+            .add_attribute("action", "claim_excess")
+            .add_attribute("contributor", context.info.sender.to_string())
+            .add_attribute("allocation", allocation.to_string())
+            .add_attribute("excess", excess.to_string())
+            .add_attribute("receipts", record.receipts.to_string());
+
+        if !record.receipts.is_zero() {
+            response = response.add_message(mint_receipts(
+                &self.cw20_address.load(context.deps.storage)?,
+                &context.info.sender,
+                record.receipts,
+            )?);
+        }
+
+        if !excess.is_zero() {
+            let denom = self.denom.load(context.deps.storage)?;
+
+            self.append_ledger(
+                context.deps.storage,
+                &context.env,
+                context.info.sender.clone(),
+                LedgerKind::Refund,
+                coin(excess.u128(), denom.clone()),
+                None,
+            )?;
+
+            response = response.add_message(BankMsg::Send {
+                to_address: context.info.sender.to_string(),
+                amount: vec![coin(excess.u128(), denom)],
+            });
+        }
+
+        Ok(response)
+    }
+
+    /// Stakes receipt tokens sent to the contract, making them earn revenue.
+    fn stake(
+        &self,
+        deps: DepsMut,
+        env: &Env,
+        sender: Addr,
+        amount: Uint128,
+    ) -> StdResult<Response> {
+        if amount.is_zero() {
+            return Err(StdError::generic_err("Cannot stake zero receipts"));
+        }
+
+        let revenue_index = self.revenue_index.load(deps.storage)?;
+        let mut staker = self
+            .stakers
+            .may_load(deps.storage, sender.clone())?
+            .unwrap_or_else(|| Staker::new(revenue_index, env.block.time));
+
+        staker.accrue(revenue_index)?;
+        staker.add_stake(amount, env.block.time);
+
+        self.stakers.save(deps.storage, sender.clone(), &staker)?;
+        self.total_staked
+            .update(deps.storage, |total| -> StdResult<_> { Ok(total + amount) })?;
+
+        Ok(Response::default()
+            .add_attribute("action", "stake")
+            .add_attribute("staker", sender.to_string())
+            .add_attribute("amount", amount.to_string())
+            .add_attribute("staked", staker.staked.to_string()))
+    }
+
+    #[sv::msg(exec)]
+    pub fn unstake(&self, context: ExecCtx, amount: Uint128) -> StdResult<Response> {
+        let revenue_index = self.revenue_index.load(context.deps.storage)?;
+        let mut staker = self
+            .stakers
+            .may_load(context.deps.storage, context.info.sender.clone())?
+            .ok_or_else(|| StdError::generic_err("No staked receipts found"))?;
+
+        if amount.is_zero() || amount > staker.staked {
+            return Err(StdError::generic_err(
+                "Amount is greater than staked receipts",
+            ));
+        }
+
+        let release_at = context
+            .env
+            .block
+            .time
+            .plus_seconds(self.unbonding_period.load(context.deps.storage)?);
+
+        staker.accrue(revenue_index)?;
+        staker.staked -= amount;
+        staker.unbonding.push(Unbonding { amount, release_at });
+
+        self.stakers
+            .save(context.deps.storage, context.info.sender.clone(), &staker)?;
+        self.total_staked
+            .update(context.deps.storage, |total| -> StdResult<_> {
+                Ok(total - amount)
+            })?;
+
+        Ok(Response::default()
+            .add_attribute("action", "unstake")
+            .add_attribute("staker", context.info.sender.to_string())
+            .add_attribute("amount", amount.to_string())
+            .add_attribute("staked", staker.staked.to_string())
+            .add_attribute("release_at", release_at.to_string()))
+    }
+
+    #[sv::msg(exec)]
+    pub fn claim_unbonded(&self, context: ExecCtx) -> StdResult<Response> {
+        let mut staker = self
+            .stakers
+            .may_load(context.deps.storage, context.info.sender.clone())?
+            .ok_or_else(|| StdError::generic_err("No staked receipts found"))?;
+
+        let amount = staker.release_unbonded(context.env.block.time);
+
+        if amount.is_zero() {
+            return Err(StdError::generic_err("No unbonded receipts to claim"));
+        }
+
+        self.stakers
+            .save(context.deps.storage, context.info.sender.clone(), &staker)?;
+
+        let cw20_address = self.cw20_address.load(context.deps.storage)?;
+
+        Ok(Response::default()
+            .add_message(send_token(
+                &Token::Cw20 {
+                    address: cw20_address,
+                },
+                &context.info.sender,
+                amount,
+            )?)
+            .add_attribute("action", "claim_unbonded")
+            .add_attribute("staker", context.info.sender.to_string())
+            .add_attribute("amount", amount.to_string()))
+    }
+
+    #[sv::msg(exec)]
+    pub fn deposit_revenue(&self, context: ExecCtx) -> StdResult<Response> {
+        let campaign = self.campaign.load(context.deps.storage)?;
+        let denom = self.denom.load(context.deps.storage)?;
+
+        // Revenue deposited before finalization would be paid out with the
+        // raised funds
+        if !campaign.finalized {
+            return Err(StdError::generic_err("Campaign has not been finalized"));
+        }
+
+        if context.info.funds.len() != 1 || context.info.funds[0].denom != denom {
+            return Err(StdError::generic_err(format!(
+                "Revenue must be deposited in {}",
+                denom
+            )));
+        }
+
+        let amount = context.info.funds[0].amount;
+        let total_staked = self.total_staked.load(context.deps.storage)?;
+
+        if amount.is_zero() {
+            return Err(StdError::generic_err("No funds sent"));
+        }
+
+        if total_staked.is_zero() {
+            return Err(StdError::generic_err("No receipts are staked"));
+        }
+
+        let revenue_index = self.revenue_index.load(context.deps.storage)?
+            + Decimal256::from_ratio(amount, total_staked);
+
+        self.revenue_index
+            .save(context.deps.storage, &revenue_index)?;
+        self.unclaimed_revenue
+            .update(context.deps.storage, |unclaimed| -> StdResult<_> {
+                Ok(unclaimed + amount)
+            })?;
+
+        Ok(Response::default()
+            .add_attribute("action", "deposit_revenue")
+            .add_attribute("campaign", campaign.name)
+            .add_attribute("depositor", context.info.sender.to_string())
+            .add_attribute("amount", amount.to_string())
+            .add_attribute("revenue_index", revenue_index.to_string()))
+    }
+
+    #[sv::msg(exec)]
+    pub fn claim_revenue(&self, context: ExecCtx) -> StdResult<Response> {
+        let revenue_index = self.revenue_index.load(context.deps.storage)?;
+        let mut staker = self
+            .stakers
+            .may_load(context.deps.storage, context.info.sender.clone())?
+            .ok_or_else(|| StdError::generic_err("No revenue to claim"))?;
+
+        staker.accrue(revenue_index)?;
+        let amount = staker.pending_revenue;
+
+        if amount.is_zero() {
+            return Err(StdError::generic_err("No revenue to claim"));
+        }
+
+        staker.pending_revenue = Uint128::zero();
+        self.stakers
+            .save(context.deps.storage, context.info.sender.clone(), &staker)?;
+        self.unclaimed_revenue
+            .update(context.deps.storage, |unclaimed| -> StdResult<_> {
+                Ok(unclaimed.saturating_sub(amount))
+            })?;
+
+        Ok(Response::default()
+            .add_message(BankMsg::Send {
+                to_address: context.info.sender.to_string(),
+                amount: vec![coin(amount.u128(), self.denom.load(context.deps.storage)?)],
+            })
+            .add_attribute("action", "claim_revenue")
+            .add_attribute("claimer", context.info.sender.to_string())
+            .add_attribute("amount", amount.to_string()))
+    }
+
     #[sv::msg(query)]
     pub fn info(&self, context: QueryCtx) -> StdResult<Campaign> {
         self.campaign.load(context.deps.storage)
@@ -301,38 +2483,300 @@ impl KickstarterContract {
 
     #[sv::msg(query)]
     pub fn config(&self, context: QueryCtx) -> StdResult<ConfigResponse> {
+        let pause_reason = self.pause_reason.may_load(context.deps.storage)?;
+
         Ok(ConfigResponse {
             cw20_address: self.cw20_address.load(context.deps.storage)?,
             denom: self.denom.load(context.deps.storage)?,
+            exchange_rate: self.exchange_rate.load(context.deps.storage)?,
+            unbonding_period: self.unbonding_period.load(context.deps.storage)?,
+            platform_admin: self.platform_admin.load(context.deps.storage)?,
+            paused: pause_reason.is_some(),
+            pause_reason,
         })
     }
 
     #[sv::msg(query)]
-    pub fn contributions(&self, context: QueryCtx) -> StdResult<Vec<ContributionResponse>> {
-        self.contributions
+    pub fn contributions(
+        &self,
+        context: QueryCtx,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<Vec<ContributionResponse>> {
+        let campaign = self.campaign.load(context.deps.storage)?;
+        let total_raised = self.total_raised.load(context.deps.storage)?;
+        let start_after = start_after
+            .map(|address| context.deps.api.addr_validate(&address))
+            .transpose()?;
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        self.stored_contributions
             .range(
                 context.deps.storage,
+                start_after.map(Bound::exclusive),
                 None,
-                None,
-                cosmwasm_std::Order::Ascending,
+                Order::Ascending,
             )
+            .take(limit)
             .map(|item| {
-                item.map(|(contributor, amount)| ContributionResponse {
-                    contributor,
-                    amount,
+                item.map(|(contributor, record)| {
+                    let mut record = record.into_current(&campaign);
+                    // Tiers may have been added since the last contribution
+                    record.tier = campaign.tier_of(&record, total_raised);
+                    ContributionResponse::new(contributor, record, campaign.finalized)
                 })
             })
             .collect()
     }
 
     #[sv::msg(query)]
-    pub fn contribution(&self, context: QueryCtx, address: String) -> StdResult<Uint128> {
+    pub fn contribution(
+        &self,
+        context: QueryCtx,
+        address: String,
+    ) -> StdResult<Option<ContributionResponse>> {
+        let campaign = self.campaign.load(context.deps.storage)?;
+        let total_raised = self.total_raised.load(context.deps.storage)?;
+        let contributor = context.deps.api.addr_validate(&address)?;
+
         Ok(self
-            .contributions
-            .load(
+            .load_contribution(context.deps.storage, &campaign, contributor.clone())?
+            .map(|mut record| {
+                // Tiers may have been added since the last contribution
+                record.tier = campaign.tier_of(&record, total_raised);
+                ContributionResponse::new(contributor, record, campaign.finalized)
+            }))
+    }
+
+    #[sv::msg(query)]
+    pub fn history_by_address(
+        &self,
+        context: QueryCtx,
+        address: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<Vec<LedgerEntry>> {
+        let address = context.deps.api.addr_validate(&address)?;
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        self.ledger
+            .idx
+            .address
+            .prefix(address)
+            .range(
+                context.deps.storage,
+                start_after.map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .take(limit)
+            .map(|item| item.map(|(_, entry)| entry))
+            .collect()
+    }
+
+    #[sv::msg(query)]
+    pub fn history_by_time(
+        &self,
+        context: QueryCtx,
+        start: Option<Timestamp>,
+        end: Option<Timestamp>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<Vec<LedgerEntry>> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        let min = match start_after {
+            Some(id) => {
+                let entry = self.ledger.load(context.deps.storage, id)?;
+                Bound::exclusive((entry.timestamp.nanos(), id))
+            }
+            None => Bound::inclusive((start.map_or(0, |start| start.nanos()), 0)),
+        };
+        let max = Bound::inclusive((end.map_or(u64::MAX, |end| end.nanos()), u64::MAX));
+
+        self.ledger
+            .idx
+            .timestamp
+            .range(context.deps.storage, Some(min), Some(max), Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(|(_, entry)| entry))
+            .collect()
+    }
+
+    #[sv::msg(query)]
+    pub fn messages(
+        &self,
+        context: QueryCtx,
+        start_before: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<Vec<MessageResponse>> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        self.messages
+            .keys(
+                context.deps.storage,
+                None,
+                start_before.map(Bound::exclusive),
+                Order::Descending,
+            )
+            .take(limit)
+            .map(|id| {
+                let entry = self.ledger.load(context.deps.storage, id?)?;
+                Ok(MessageResponse {
+                    id: entry.id,
+                    contributor: entry.address,
+                    amount: entry.amount,
+                    memo: entry.memo.unwrap_or_default(),
+                    timestamp: entry.timestamp,
+                })
+            })
+            .collect()
+    }
+
+    #[sv::msg(query)]
+    pub fn distribution(&self, context: QueryCtx) -> StdResult<Option<Distribution>> {
+        self.distribution.may_load(context.deps.storage)
+    }
+
+    #[sv::msg(query)]
+    pub fn claimable_tokens(&self, context: QueryCtx, address: String) -> StdResult<Uint128> {
+        let address = context.deps.api.addr_validate(&address)?;
+
+        match self.distribution.may_load(context.deps.storage)? {
+            Some(distribution) if distribution.claim_deadline > context.env.block.time => Ok(self
+                .vested_tokens_for(
+                    context.deps.storage,
+                    &distribution,
+                    &address,
+                    context.env.block.time,
+                )?
+                .claimable),
+            _ => Ok(Uint128::zero()),
+        }
+    }
+
+    #[sv::msg(query)]
+    pub fn vested(&self, context: QueryCtx, address: String) -> StdResult<VestedResponse> {
+        let address = context.deps.api.addr_validate(&address)?;
+        let distribution = self
+            .distribution
+            .may_load(context.deps.storage)?
+            .ok_or_else(|| StdError::generic_err("No tokens are being distributed"))?;
+
+        self.vested_tokens_for(
+            context.deps.storage,
+            &distribution,
+            &address,
+            context.env.block.time,
+        )
+    }
+
+    #[sv::msg(query)]
+    pub fn pending_revenue(&self, context: QueryCtx, address: String) -> StdResult<Uint128> {
+        let address = context.deps.api.addr_validate(&address)?;
+        let revenue_index = self.revenue_index.load(context.deps.storage)?;
+
+        match self.stakers.may_load(context.deps.storage, address)? {
+            Some(mut staker) => {
+                staker.accrue(revenue_index)?;
+                Ok(staker.pending_revenue)
+            }
+            None => Ok(Uint128::zero()),
+        }
+    }
+
+    #[sv::msg(query)]
+    pub fn staker(&self, context: QueryCtx, address: String) -> StdResult<StakerResponse> {
+        let address = context.deps.api.addr_validate(&address)?;
+        let revenue_index = self.revenue_index.load(context.deps.storage)?;
+
+        Ok(
+            match self.stakers.may_load(context.deps.storage, address)? {
+                Some(mut staker) => {
+                    staker.accrue(revenue_index)?;
+                    StakerResponse {
+                        staked: staker.staked,
+                        staked_since: (!staker.staked.is_zero()).then_some(staker.staked_since),
+                        voting_power: staker
+                            .voting_power(context.env.block.time, VOTING_POWER_MATURITY),
+                        unbonding: staker.unbonding,
+                        pending_revenue: staker.pending_revenue,
+                    }
+                }
+                None => StakerResponse {
+                    staked: Uint128::zero(),
+                    staked_since: None,
+                    unbonding: vec![],
+                    pending_revenue: Uint128::zero(),
+                    voting_power: Uint128::zero(),
+                },
+            },
+        )
+    }
+
+    #[sv::msg(query)]
+    pub fn operators(
+        &self,
+        context: QueryCtx,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<Vec<OperatorResponse>> {
+        let start_after = start_after
+            .map(|address| context.deps.api.addr_validate(&address))
+            .transpose()?;
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        self.operators
+            .range(
+                context.deps.storage,
+                start_after.map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .take(limit)
+            .map(|item| item.map(|(address, roles)| OperatorResponse { address, roles }))
+            .collect()
+    }
+
+    #[sv::msg(query)]
+    pub fn moderation_log(
+        &self,
+        context: QueryCtx,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<Vec<ModerationEntry>> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        self.moderation_log
+            .range(
+                context.deps.storage,
+                start_after.map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .take(limit)
+            .map(|item| item.map(|(_, entry)| entry))
+            .collect()
+    }
+
+    #[sv::msg(query)]
+    pub fn campaign_history(
+        &self,
+        context: QueryCtx,
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    ) -> StdResult<Vec<CampaignRevision>> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        self.campaign_history
+            .range(
                 context.deps.storage,
-                context.deps.api.addr_validate(&address)?,
+                start_after.map(Bound::exclusive),
+                None,
+                Order::Ascending,
             )
-            .unwrap_or(Uint128::zero()))
+            .take(limit)
+            .map(|item| item.map(|(_, revision)| revision))
+            .collect()
     }
 }
@@ -1,14 +1,112 @@
+use crate::storage::{Contribution, Role, Unbonding, VestingSchedule};
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Uint128};
+use cosmwasm_std::{Addr, Decimal, Timestamp, Uint128};
 
 #[cw_serde]
 pub struct ConfigResponse {
     pub cw20_address: Addr,
     pub denom: String,
+    pub exchange_rate: Decimal,
+    pub unbonding_period: u64,
+    pub platform_admin: Addr,
+    pub paused: bool,
+    pub pause_reason: Option<String>,
 }
 
 #[cw_serde]
 pub struct ContributionResponse {
     pub contributor: Addr,
     pub amount: Uint128,
+    pub receipts: Uint128,
+    pub tier: Option<String>,
+    pub first_contribution: Timestamp,
+    pub last_contribution: Timestamp,
+    pub finalized: bool,
+}
+
+/// Proof that an address is on the presale allowlist. Each leaf of the
+/// Merkle tree is the SHA-256 hash of the address followed by its allowance.
+#[cw_serde]
+pub struct AllowlistProof {
+    pub allowance: Uint128,
+    pub proof: Vec<String>,
+}
+
+/// Subset of the cw721 query API used to check NFT ownership, also
+/// implemented by sg721 collections.
+#[cw_serde]
+pub enum Cw721QueryMsg {
+    Tokens {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub struct TokensResponse {
+    pub tokens: Vec<String>,
+}
+
+/// Hook messages accepted with receipt tokens.
+#[cw_serde]
+pub enum ReceiptMsg {
+    Refund {},
+    Stake {},
+}
+
+/// Hook messages accepted with CW20 tokens other than the receipt token.
+#[cw_serde]
+pub enum ReceiveMsg {
+    DepositTokens {
+        claim_deadline: Option<Timestamp>,
+        vesting: Option<VestingSchedule>,
+    },
+}
+
+#[cw_serde]
+pub struct VestedResponse {
+    pub total: Uint128,
+    pub vested: Uint128,
+    pub claimed: Uint128,
+    pub claimable: Uint128,
+    pub locked: Uint128,
+}
+
+#[cw_serde]
+pub struct MessageResponse {
+    pub id: u64,
+    pub contributor: Addr,
+    pub amount: Uint128,
+    pub memo: String,
+    pub timestamp: Timestamp,
+}
+
+#[cw_serde]
+pub struct StakerResponse {
+    pub staked: Uint128,
+    pub staked_since: Option<Timestamp>,
+    pub unbonding: Vec<Unbonding>,
+    pub pending_revenue: Uint128,
+    pub voting_power: Uint128,
+}
+
+#[cw_serde]
+pub struct OperatorResponse {
+    pub address: Addr,
+    pub roles: Vec<Role>,
+}
+
+impl ContributionResponse {
+    pub fn new(contributor: Addr, record: Contribution, finalized: bool) -> Self {
+        Self {
+            contributor,
+            amount: record.amount,
+            receipts: record.receipts,
+            tier: record.tier,
+            first_contribution: record.first_contribution,
+            last_contribution: record.last_contribution,
+            finalized,
+        }
+    }
 }
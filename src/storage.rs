@@ -1,5 +1,10 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Timestamp, Uint128};
+use cosmwasm_std::{
+    Addr, Binary, Decimal, Decimal256, StdError, StdResult, Timestamp, Uint128, Uint256,
+};
+use cw_storage_plus::{Index, IndexList, MultiIndex};
+use serde::{Deserialize, Serialize};
+use std::fmt;
 
 #[cw_serde]
 pub struct Link {
@@ -18,6 +23,407 @@ pub struct Tier {
 
 #[cw_serde]
 pub struct Campaign {
+    pub name: String,
+    pub description: Markdown,
+    pub start_time: Timestamp,
+    pub end_time: Timestamp,
+    pub goal: Uint128,
+    pub links: Vec<Link>,
+    pub tiers: Vec<Tier>,
+    pub creator: Addr,
+    pub pending_creator: Option<PendingCreator>,
+    pub minimum_contribution: Option<Uint128>,
+    pub max_raise: Option<Uint128>,
+    pub max_contribution_per_address: Option<Uint128>,
+    pub presale: Option<Presale>,
+    pub nft_gate: Option<NftGate>,
+    pub bonus_schedule: Vec<BonusPeriod>,
+    /// Accept contributions beyond `max_raise` and scale every backer's
+    /// allocation down pro-rata once the campaign ends.
+    pub oversubscription: bool,
+    /// Addresses the payout is split between. Empty to pay the creator.
+    pub beneficiaries: Vec<Beneficiary>,
+    /// Receives the rounding dust of the split, the creator by default.
+    pub dust_recipient: Option<Addr>,
+    /// Treasury contract receiving the payout instead of the creator.
+    pub payout_address: Option<Addr>,
+    /// Message executed on the treasury with the payout attached, instead
+    /// of a plain transfer.
+    pub payout_callback: Option<Binary>,
+    pub status: CampaignStatus,
+    /// Number of times the end time has been extended.
+    pub deadline_extensions: u32,
+    /// Window opened by the latest extension, during which earlier backers
+    /// can be refunded.
+    pub refund_window: Option<RefundWindow>,
+    pub finalized: bool,
+}
+
+/// Moderation state of the campaign, set by the platform admin.
+#[cw_serde]
+pub enum CampaignStatus {
+    Active,
+    /// Contributions and the creator's payout are blocked.
+    Frozen,
+    /// The campaign is taken down and every backer can claim a full refund.
+    Voided,
+}
+
+#[cw_serde]
+pub enum ModerationAction {
+    Freeze,
+    Unfreeze,
+    Void,
+}
+
+/// Entry of the on-chain audit log of moderation actions.
+#[cw_serde]
+pub struct ModerationEntry {
+    pub id: u64,
+    pub action: ModerationAction,
+    pub admin: Addr,
+    pub reason: String,
+    pub height: u64,
+    pub timestamp: Timestamp,
+}
+
+/// Version of the campaign recorded on creation and after every update.
+#[cw_serde]
+pub struct CampaignRevision {
+    pub version: u32,
+    pub editor: Addr,
+    /// Names of the fields changed by this version, empty on creation.
+    pub changes: Vec<String>,
+    pub height: u64,
+    pub timestamp: Timestamp,
+    pub campaign: Campaign,
+}
+
+/// Window after a deadline extension during which backers who contributed
+/// before `extended_at` can be refunded.
+#[cw_serde]
+pub struct RefundWindow {
+    pub extended_at: Timestamp,
+    pub ends_at: Timestamp,
+}
+
+impl RefundWindow {
+    /// Checks whether a backer who first contributed at `first_contribution`
+    /// can be refunded at `time`.
+    pub fn covers(&self, first_contribution: Timestamp, time: Timestamp) -> bool {
+        first_contribution <= self.extended_at && time < self.ends_at
+    }
+}
+
+/// Creator proposed by the current one, who takes over once they accept
+/// the transfer before `expires_at`.
+#[cw_serde]
+pub struct PendingCreator {
+    pub address: Addr,
+    pub expires_at: Option<Timestamp>,
+}
+
+/// Permission granted to an operator of the campaign. The creator holds
+/// every role.
+#[cw_serde]
+pub enum Role {
+    /// Manages the campaign and its operators.
+    Owner,
+    /// Edits the campaign's description and links.
+    Editor,
+    /// Ends the campaign and manages project token distributions.
+    Finance,
+}
+
+/// Early-access window before `start_time` during which only addresses
+/// proven against `merkle_root` can contribute.
+#[cw_serde]
+pub struct Presale {
+    pub start_time: Timestamp,
+    pub merkle_root: String,
+}
+
+/// Window at the start of the campaign during which only holders of an NFT
+/// from one of `collections` can contribute. `holder_tier` optionally names a
+/// tier that only holders can reach.
+#[cw_serde]
+pub struct NftGate {
+    pub collections: Vec<String>,
+    pub end_time: Timestamp,
+    pub holder_tier: Option<String>,
+}
+
+/// Extra receipts granted to contributions made within `duration` seconds of
+/// the campaign's start, e.g. a `bonus` of 0.2 mints 20% more receipts.
+#[cw_serde]
+pub struct BonusPeriod {
+    pub duration: u64,
+    pub bonus: Decimal,
+}
+
+/// Share of the payout, in basis points, sent to `address`.
+#[cw_serde]
+pub struct Beneficiary {
+    pub address: Addr,
+    pub share_bps: u16,
+}
+
+#[cw_serde]
+pub struct BeneficiaryMeta {
+    pub address: String,
+    pub share_bps: u16,
+}
+
+/// Total of the beneficiaries' shares, in basis points.
+pub const TOTAL_SHARE_BPS: u16 = 10_000;
+
+impl Campaign {
+    /// Returns the highest tier reached by the given contribution amount.
+    pub fn tier_for(&self, amount: Uint128, nft_holder: bool) -> Option<&Tier> {
+        let holder_tier = self
+            .nft_gate
+            .as_ref()
+            .and_then(|gate| gate.holder_tier.as_ref());
+
+        self.tiers
+            .iter()
+            .filter(|tier| nft_holder || holder_tier != Some(&tier.name))
+            .filter(|tier| tier.required_contribution <= amount)
+            .max_by_key(|tier| tier.required_contribution)
+    }
+
+    /// Returns the name of the tier a backer currently qualifies for. Settled
+    /// oversubscribed contributions only count their allocation.
+    pub fn tier_of(&self, record: &Contribution, total_raised: Uint128) -> Option<String> {
+        let amount = if record.settled {
+            record.amount - self.excess_of(record.amount, total_raised)
+        } else {
+            record.amount
+        };
+
+        self.tier_for(amount, record.nft_holder)
+            .map(|tier| tier.name.clone())
+    }
+
+    /// Returns the receipt bonus for a contribution made at the given time.
+    /// When bonus periods overlap, the highest bonus applies.
+    pub fn bonus_at(&self, time: Timestamp) -> Decimal {
+        let elapsed = time.seconds().saturating_sub(self.start_time.seconds());
+
+        self.bonus_schedule
+            .iter()
+            .filter(|period| elapsed < period.duration)
+            .map(|period| period.bonus)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Returns the share of `amount` which is not allocated once an
+    /// oversubscribed campaign has raised `total_raised`. Rounded down, so
+    /// the excess of all backers never exceeds what was raised over the cap.
+    pub fn excess_of(&self, amount: Uint128, total_raised: Uint128) -> Uint128 {
+        match self.max_raise {
+            Some(max_raise) if self.oversubscription && total_raised > max_raise => {
+                amount.multiply_ratio(total_raised - max_raise, total_raised)
+            }
+            _ => Uint128::zero(),
+        }
+    }
+
+    /// Ensures `tiers` keeps every tier a backer who contributed up to
+    /// `largest_contribution` already qualifies for, with the same price.
+    pub fn ensure_tiers_kept(
+        &self,
+        tiers: &[Tier],
+        largest_contribution: Uint128,
+    ) -> StdResult<()> {
+        for tier in &self.tiers {
+            if tier.required_contribution > largest_contribution {
+                continue;
+            }
+
+            let kept = tiers.iter().any(|new| {
+                new.name == tier.name && new.required_contribution == tier.required_contribution
+            });
+
+            if !kept {
+                return Err(StdError::generic_err(format!(
+                    "Tier {} cannot be removed or repriced as backers already qualify for it",
+                    tier.name
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fails unless the campaign is active, i.e. neither frozen nor voided.
+    pub fn ensure_active(&self) -> StdResult<()> {
+        match self.status {
+            CampaignStatus::Active => Ok(()),
+            CampaignStatus::Frozen => Err(StdError::generic_err("Campaign is frozen")),
+            CampaignStatus::Voided => Err(StdError::generic_err("Campaign has been voided")),
+        }
+    }
+
+    /// Splits the payout between the beneficiaries. Shares are rounded down
+    /// and the remainder goes to the dust recipient.
+    pub fn split_payout(&self, payout: Uint128) -> Vec<(Addr, Uint128)> {
+        if self.beneficiaries.is_empty() {
+            let recipient = self.payout_address.as_ref().unwrap_or(&self.creator);
+            return vec![(recipient.clone(), payout)];
+        }
+
+        let mut split: Vec<(Addr, Uint128)> = self
+            .beneficiaries
+            .iter()
+            .map(|beneficiary| {
+                (
+                    beneficiary.address.clone(),
+                    payout.multiply_ratio(beneficiary.share_bps, TOTAL_SHARE_BPS),
+                )
+            })
+            .collect();
+
+        let dust = payout - split.iter().map(|(_, amount)| *amount).sum::<Uint128>();
+        let dust_recipient = self
+            .dust_recipient
+            .clone()
+            .unwrap_or_else(|| self.creator.clone());
+
+        match split
+            .iter_mut()
+            .find(|(address, _)| *address == dust_recipient)
+        {
+            Some((_, amount)) => *amount += dust,
+            None => split.push((dust_recipient, dust)),
+        }
+
+        split.retain(|(_, amount)| !amount.is_zero());
+        split
+    }
+
+    /// Ensures the campaign settings are consistent with each other: payout
+    /// routing, contribution limits and the NFT gate.
+    pub fn validate(&self) -> StdResult<()> {
+        if !self.beneficiaries.is_empty() {
+            let total: u32 = self
+                .beneficiaries
+                .iter()
+                .map(|beneficiary| beneficiary.share_bps as u32)
+                .sum();
+
+            if total != TOTAL_SHARE_BPS as u32 {
+                return Err(StdError::generic_err(format!(
+                    "Beneficiary shares must sum to {} basis points",
+                    TOTAL_SHARE_BPS
+                )));
+            }
+
+            if self
+                .beneficiaries
+                .iter()
+                .any(|beneficiary| beneficiary.share_bps == 0)
+            {
+                return Err(StdError::generic_err(
+                    "Beneficiary shares must be greater than zero",
+                ));
+            }
+
+            for (i, beneficiary) in self.beneficiaries.iter().enumerate() {
+                if self.beneficiaries[..i]
+                    .iter()
+                    .any(|other| other.address == beneficiary.address)
+                {
+                    return Err(StdError::generic_err(format!(
+                        "Duplicate beneficiary: {}",
+                        beneficiary.address
+                    )));
+                }
+            }
+        } else if self.dust_recipient.is_some() {
+            return Err(StdError::generic_err(
+                "Dust recipient requires beneficiaries",
+            ));
+        }
+
+        if self.payout_address.is_some() && !self.beneficiaries.is_empty() {
+            return Err(StdError::generic_err(
+                "Payout address cannot be combined with beneficiaries",
+            ));
+        }
+
+        if self.payout_callback.is_some() && self.payout_address.is_none() {
+            return Err(StdError::generic_err(
+                "Payout callback requires a payout address",
+            ));
+        }
+
+        if self.oversubscription && self.max_raise.is_none() {
+            return Err(StdError::generic_err(
+                "Oversubscription requires a hard cap",
+            ));
+        }
+
+        if let Some(max_raise) = self.max_raise {
+            if max_raise.is_zero() {
+                return Err(StdError::generic_err("Hard cap must be greater than zero"));
+            }
+
+            if max_raise < self.goal {
+                return Err(StdError::generic_err(
+                    "Hard cap cannot be lower than the goal",
+                ));
+            }
+        }
+
+        if let Some(max_contribution) = self.max_contribution_per_address {
+            if max_contribution.is_zero() {
+                return Err(StdError::generic_err(
+                    "Per-address limit must be greater than zero",
+                ));
+            }
+
+            if let Some(minimum_contribution) = self.minimum_contribution {
+                if max_contribution < minimum_contribution {
+                    return Err(StdError::generic_err(
+                        "Per-address limit cannot be lower than the minimum contribution",
+                    ));
+                }
+            }
+        }
+
+        if let Some(gate) = &self.nft_gate {
+            if gate.collections.is_empty() {
+                return Err(StdError::generic_err(
+                    "NFT gate must list at least one collection",
+                ));
+            }
+
+            if gate.end_time > self.end_time {
+                return Err(StdError::generic_err(
+                    "NFT gate cannot end after the campaign",
+                ));
+            }
+
+            if let Some(holder_tier) = &gate.holder_tier {
+                if !self.tiers.iter().any(|tier| &tier.name == holder_tier) {
+                    return Err(StdError::generic_err(format!(
+                        "Unknown holder tier: {}",
+                        holder_tier
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Campaign as stored by the first release of the contract, before it
+/// tracked a contract version.
+#[cw_serde]
+pub struct LegacyCampaign {
     pub name: String,
     pub description: Markdown,
     pub end_time: Timestamp,
@@ -32,9 +438,219 @@ pub struct Campaign {
 pub struct CampaignMeta {
     pub name: String,
     pub description: Markdown,
+    pub start_time: Option<Timestamp>,
     pub end_time: Timestamp,
     pub goal: Uint128,
     pub links: Vec<Link>,
     pub tiers: Vec<Tier>,
     pub minimum_contribution: Option<Uint128>,
+    pub max_raise: Option<Uint128>,
+    pub max_contribution_per_address: Option<Uint128>,
+    pub presale: Option<Presale>,
+    pub nft_gate: Option<NftGate>,
+    pub bonus_schedule: Vec<BonusPeriod>,
+    pub oversubscription: bool,
+    pub beneficiaries: Vec<BeneficiaryMeta>,
+    pub dust_recipient: Option<String>,
+    pub payout_address: Option<String>,
+    pub payout_callback: Option<Binary>,
+}
+
+#[cw_serde]
+pub struct Contribution {
+    pub amount: Uint128,
+    pub receipts: Uint128,
+    pub tier: Option<String>,
+    pub first_contribution: Timestamp,
+    pub last_contribution: Timestamp,
+    pub nft_holder: bool,
+    /// Whether the excess of an oversubscribed contribution has been claimed
+    /// and its receipts minted.
+    pub settled: bool,
+}
+
+/// Contribution record as found in storage. Records of the first release
+/// were plain amounts, which are converted when they are next read.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum StoredContribution {
+    Current(Contribution),
+    Legacy(Uint128),
+}
+
+impl StoredContribution {
+    pub fn into_current(self, campaign: &Campaign) -> Contribution {
+        match self {
+            StoredContribution::Current(record) => record,
+            // Receipts were minted one-to-one with the contribution
+            StoredContribution::Legacy(amount) => Contribution {
+                amount,
+                receipts: amount,
+                tier: campaign
+                    .tier_for(amount, false)
+                    .map(|tier| tier.name.clone()),
+                first_contribution: campaign.start_time,
+                last_contribution: campaign.start_time,
+                nft_holder: false,
+                settled: false,
+            },
+        }
+    }
+}
+
+#[cw_serde]
+pub enum LedgerKind {
+    Contribution,
+    Refund,
+}
+
+#[cw_serde]
+pub struct LedgerEntry {
+    pub id: u64,
+    pub address: Addr,
+    pub kind: LedgerKind,
+    pub amount: Uint128,
+    pub denom: String,
+    pub height: u64,
+    pub timestamp: Timestamp,
+    pub memo: Option<String>,
+}
+
+pub struct LedgerIndexes<'a> {
+    pub address: MultiIndex<'a, Addr, LedgerEntry, u64>,
+    pub timestamp: MultiIndex<'a, u64, LedgerEntry, u64>,
+}
+
+impl IndexList<LedgerEntry> for LedgerIndexes<'_> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<LedgerEntry>> + '_> {
+        let v: Vec<&dyn Index<LedgerEntry>> = vec![&self.address, &self.timestamp];
+        Box::new(v.into_iter())
+    }
+}
+
+#[cw_serde]
+pub enum Token {
+    Native { denom: String },
+    Cw20 { address: Addr },
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Native { denom } => write!(f, "{}", denom),
+            Token::Cw20 { address } => write!(f, "{}", address),
+        }
+    }
+}
+
+/// Project tokens deposited by the creator after the campaign, claimable by
+/// backers pro-rata to their contributions until `claim_deadline`.
+#[cw_serde]
+pub struct Distribution {
+    pub token: Token,
+    pub total: Uint128,
+    pub claimed: Uint128,
+    pub claim_deadline: Timestamp,
+    pub total_contributions: Uint128,
+    pub vesting: Option<VestingSchedule>,
+}
+
+/// Linear unlock of distributed tokens over `duration` seconds from
+/// `start_time`, with nothing claimable during the first `cliff` seconds.
+#[cw_serde]
+pub struct VestingSchedule {
+    pub start_time: Timestamp,
+    pub cliff: u64,
+    pub duration: u64,
+}
+
+impl VestingSchedule {
+    /// Returns the part of `total` vested at the given time.
+    pub fn vested(&self, total: Uint128, time: Timestamp) -> Uint128 {
+        let elapsed = time.seconds().saturating_sub(self.start_time.seconds());
+
+        if time < self.start_time || elapsed < self.cliff {
+            Uint128::zero()
+        } else if elapsed >= self.duration {
+            total
+        } else {
+            total.multiply_ratio(elapsed, self.duration)
+        }
+    }
+}
+
+/// Receipt tokens staked in the contract to earn a share of the revenue.
+#[cw_serde]
+pub struct Staker {
+    pub staked: Uint128,
+    /// Stake-weighted average time at which the receipts were staked, used
+    /// to grow voting power with time.
+    pub staked_since: Timestamp,
+    pub revenue_index: Decimal256,
+    pub pending_revenue: Uint128,
+    pub unbonding: Vec<Unbonding>,
+}
+
+/// Receipts being unstaked, returned once the unbonding period is over.
+#[cw_serde]
+pub struct Unbonding {
+    pub amount: Uint128,
+    pub release_at: Timestamp,
+}
+
+impl Staker {
+    pub fn new(revenue_index: Decimal256, time: Timestamp) -> Self {
+        Self {
+            staked: Uint128::zero(),
+            staked_since: time,
+            revenue_index,
+            pending_revenue: Uint128::zero(),
+            unbonding: vec![],
+        }
+    }
+
+    /// Accrues the revenue earned since the last update, based on the
+    /// growth of the global revenue index.
+    pub fn accrue(&mut self, revenue_index: Decimal256) -> StdResult<()> {
+        let earned = Uint256::from(self.staked).mul_floor(revenue_index - self.revenue_index);
+
+        self.pending_revenue += Uint128::try_from(earned)?;
+        self.revenue_index = revenue_index;
+
+        Ok(())
+    }
+
+    /// Adds `amount` to the stake, moving the stake time forward in
+    /// proportion to the newly staked receipts.
+    pub fn add_stake(&mut self, amount: Uint128, time: Timestamp) {
+        let total = self.staked + amount;
+        let since = Uint128::from(self.staked_since.seconds()).multiply_ratio(self.staked, total)
+            + Uint128::from(time.seconds()).multiply_ratio(amount, total);
+
+        self.staked = total;
+        self.staked_since = Timestamp::from_seconds(since.u128() as u64);
+    }
+
+    /// Voting power of the stake at `time`. Staked receipts count once, and
+    /// gain up to the same amount again as they stay staked over
+    /// `maturity` seconds.
+    pub fn voting_power(&self, time: Timestamp, maturity: u64) -> Uint128 {
+        let age = time
+            .seconds()
+            .saturating_sub(self.staked_since.seconds())
+            .min(maturity);
+
+        self.staked + self.staked.multiply_ratio(age, maturity)
+    }
+
+    /// Removes and returns the unbonded receipts released by `time`.
+    pub fn release_unbonded(&mut self, time: Timestamp) -> Uint128 {
+        let (released, unbonding): (Vec<_>, Vec<_>) = self
+            .unbonding
+            .drain(..)
+            .partition(|unbonding| unbonding.release_at <= time);
+
+        self.unbonding = unbonding;
+        released.iter().map(|unbonding| unbonding.amount).sum()
+    }
 }
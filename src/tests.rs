@@ -1,10 +1,17 @@
 #![cfg(test)]
 
-use cosmwasm_std::{coin, coins, Addr, Binary, Coin, Empty, Timestamp, Uint128};
+use cosmwasm_std::{coin, coins, Addr, Binary, Coin, Decimal, Empty, Timestamp, Uint128};
 use cw20::{BalanceResponse, MinterResponse};
 use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+use sha2::{Digest, Sha256};
 
-use crate::msg::ContributionResponse;
+use crate::msg::{
+    AllowlistProof, ContributionResponse, MessageResponse, OperatorResponse, StakerResponse,
+    VestedResponse,
+};
+use crate::storage::{
+    CampaignStatus, LedgerEntry, LedgerKind, ModerationAction, ModerationEntry, Role,
+};
 
 pub fn contract_cw20() -> Box<dyn Contract<Empty>> {
     let contract = ContractWrapper::new(
@@ -15,19 +22,194 @@ pub fn contract_cw20() -> Box<dyn Contract<Empty>> {
     Box::new(contract)
 }
 
+// Minimal cw721 stand-in which can mint tokens and list them by owner
+mod mock_cw721 {
+    use cosmwasm_schema::cw_serde;
+    use cosmwasm_std::{
+        to_json_binary, Addr, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Order, Response,
+        StdResult,
+    };
+    use cw_storage_plus::Map;
+
+    use crate::msg::{Cw721QueryMsg, TokensResponse};
+
+    const OWNERS: Map<&str, Addr> = Map::new("owners");
+
+    #[cw_serde]
+    pub enum ExecuteMsg {
+        Mint { token_id: String, owner: String },
+    }
+
+    pub fn instantiate(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: Empty,
+    ) -> StdResult<Response> {
+        Ok(Response::default())
+    }
+
+    pub fn execute(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: ExecuteMsg,
+    ) -> StdResult<Response> {
+        match msg {
+            ExecuteMsg::Mint { token_id, owner } => {
+                let owner = deps.api.addr_validate(&owner)?;
+                OWNERS.save(deps.storage, &token_id, &owner)?;
+                Ok(Response::default())
+            }
+        }
+    }
+
+    pub fn query(deps: Deps, _env: Env, msg: Cw721QueryMsg) -> StdResult<Binary> {
+        match msg {
+            Cw721QueryMsg::Tokens { owner, limit, .. } => {
+                let tokens = OWNERS
+                    .range(deps.storage, None, None, Order::Ascending)
+                    .filter(|item| matches!(item, Ok((_, token_owner)) if token_owner.as_str() == owner))
+                    .take(limit.unwrap_or(10) as usize)
+                    .map(|item| item.map(|(token_id, _)| token_id))
+                    .collect::<StdResult<Vec<_>>>()?;
+                to_json_binary(&TokensResponse { tokens })
+            }
+        }
+    }
+}
+
+pub fn contract_cw721() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        mock_cw721::execute,
+        mock_cw721::instantiate,
+        mock_cw721::query,
+    );
+    Box::new(contract)
+}
+
+// Minimal treasury which records the funds deposited through its callback
+mod mock_treasury {
+    use cosmwasm_schema::cw_serde;
+    use cosmwasm_std::{
+        to_json_binary, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdResult,
+        Uint128,
+    };
+    use cw_storage_plus::Item;
+
+    const DEPOSITED: Item<Uint128> = Item::new("deposited");
+
+    #[cw_serde]
+    pub enum ExecuteMsg {
+        Deposit {},
+    }
+
+    pub fn instantiate(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: Empty,
+    ) -> StdResult<Response> {
+        DEPOSITED.save(deps.storage, &Uint128::zero())?;
+        Ok(Response::default())
+    }
+
+    pub fn execute(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        msg: ExecuteMsg,
+    ) -> StdResult<Response> {
+        match msg {
+            ExecuteMsg::Deposit {} => {
+                let amount: Uint128 = info.funds.iter().map(|coin| coin.amount).sum();
+                DEPOSITED.update(deps.storage, |deposited| -> StdResult<_> {
+                    Ok(deposited + amount)
+                })?;
+                Ok(Response::default())
+            }
+        }
+    }
+
+    pub fn query(deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+        to_json_binary(&DEPOSITED.load(deps.storage)?)
+    }
+}
+
+pub fn contract_treasury() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        mock_treasury::execute,
+        mock_treasury::instantiate,
+        mock_treasury::query,
+    );
+    Box::new(contract)
+}
+
 pub fn contract_kickstarter() -> Box<dyn Contract<Empty>> {
     let contract = ContractWrapper::new(
         crate::contract::entry_points::execute,
         crate::contract::entry_points::instantiate,
         crate::contract::entry_points::query,
-    );
+    )
+    .with_migrate(crate::contract::entry_points::migrate)
+    .with_reply(crate::contract::entry_points::reply);
     Box::new(contract)
 }
 
 const INIT: &str = "init";
 
+// Default campaign used by most tests
+fn default_campaign() -> crate::storage::CampaignMeta {
+    crate::storage::CampaignMeta {
+        name: "My Campaign".to_string(),
+        description: "My Campaign Description".to_string(),
+        start_time: None,
+        end_time: Timestamp::from_seconds(86400),
+        goal: Uint128::new(10000),
+        links: vec![],
+        tiers: vec![
+            crate::storage::Tier {
+                name: "Bronze".to_string(),
+                description: "Bronze Tier".to_string(),
+                required_contribution: Uint128::new(100),
+            },
+            crate::storage::Tier {
+                name: "Silver".to_string(),
+                description: "Silver Tier".to_string(),
+                required_contribution: Uint128::new(500),
+            },
+        ],
+        minimum_contribution: Some(Uint128::new(100)),
+        max_raise: None,
+        max_contribution_per_address: None,
+        presale: None,
+        nft_gate: None,
+        bonus_schedule: vec![],
+        oversubscription: false,
+        beneficiaries: vec![],
+        dust_recipient: None,
+        payout_address: None,
+        payout_callback: None,
+    }
+}
+
 // Initial contract setup
 fn setup_contracts() -> (App, Addr, Addr, Addr, Addr, Addr) {
+    setup_contracts_with(default_campaign())
+}
+
+// Initial contract setup with a custom campaign
+fn setup_contracts_with(
+    campaign: crate::storage::CampaignMeta,
+) -> (App, Addr, Addr, Addr, Addr, Addr) {
+    let (mut router, admin, user, fee) = setup_app();
+    let (cw20_addr, kickstarter_addr) = setup_kickstarter(&mut router, &admin, campaign, None);
+
+    (router, cw20_addr, kickstarter_addr, admin, user, fee)
+}
+
+// Initial chain setup with funded accounts
+fn setup_app() -> (App, Addr, Addr, Addr) {
     let init = Addr::unchecked(INIT);
 
     let init_funds = coins(2000, "ustars");
@@ -50,6 +232,20 @@ fn setup_contracts() -> (App, Addr, Addr, Addr, Addr, Addr) {
         .send_tokens(init, admin.clone(), &coins(1000, "ustars"))
         .unwrap();
 
+    let mut block = router.block_info();
+    block.time = Timestamp::from_seconds(1);
+    router.set_block(block);
+
+    (router, admin, user, fee)
+}
+
+// Set up the CW20 and Kickstarter contracts for a campaign
+fn setup_kickstarter(
+    router: &mut App,
+    admin: &Addr,
+    campaign: crate::storage::CampaignMeta,
+    exchange_rate: Option<Decimal>,
+) -> (Addr, Addr) {
     // Set up CW20 contract
     let cw20_id = router.store_code(contract_cw20());
     let msg = cw20_base::msg::InstantiateMsg {
@@ -72,15 +268,9 @@ fn setup_contracts() -> (App, Addr, Addr, Addr, Addr, Addr) {
     let msg = crate::contract::sv::InstantiateMsg {
         cw20_address: cw20_addr.to_string(),
         denom: "ustars".to_string(),
-        campaign: crate::storage::CampaignMeta {
-            name: "My Campaign".to_string(),
-            description: "My Campaign Description".to_string(),
-            end_time: Timestamp::from_seconds(86400),
-            goal: Uint128::new(10000),
-            links: vec![],
-            tiers: vec![],
-            minimum_contribution: Some(Uint128::new(100)),
-        },
+        campaign,
+        exchange_rate,
+        unbonding_period: None,
     };
 
     let kickstarter_addr = router
@@ -102,11 +292,19 @@ fn setup_contracts() -> (App, Addr, Addr, Addr, Addr, Addr) {
         .execute_contract(admin.clone(), cw20_addr.clone(), &msg, &[])
         .unwrap();
 
-    let mut block = router.block_info();
-    block.time = Timestamp::from_seconds(1);
-    router.set_block(block);
+    (cw20_addr, kickstarter_addr)
+}
 
-    (router, cw20_addr, kickstarter_addr, admin, user, fee)
+// Hash a presale allowlist leaf
+fn allowlist_leaf(address: &Addr, allowance: u128) -> [u8; 32] {
+    Sha256::digest(format!("{}{}", address, allowance)).into()
+}
+
+// Hash two sibling nodes of the presale allowlist tree
+fn allowlist_node(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+    let mut pair = [a, b];
+    pair.sort_unstable();
+    Sha256::digest(pair.concat()).into()
 }
 
 // Update block time
@@ -126,7 +324,10 @@ fn try_contribute() {
     let (mut router, _, kickstarter_addr, _, user, _) = setup_contracts();
 
     // Contribute to the campaign
-    let msg = crate::contract::sv::ExecMsg::Contribute {};
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
     router
         .execute_contract(
             user.clone(),
@@ -150,14 +351,17 @@ fn try_contribute() {
         .wrap()
         .query_wasm_smart(
             kickstarter_addr.clone(),
-            &crate::contract::sv::QueryMsg::Contributions {},
+            &crate::contract::sv::QueryMsg::Contributions {
+                start_after: None,
+                limit: None,
+            },
         )
         .unwrap();
     assert_eq!(contributors.len(), 1);
     assert_eq!(contributors[0].contributor, user);
 
     // Ensure the user's contribution is recorded
-    let contribution: Uint128 = router
+    let contribution: Option<ContributionResponse> = router
         .wrap()
         .query_wasm_smart(
             kickstarter_addr.clone(),
@@ -166,7 +370,10 @@ fn try_contribute() {
             },
         )
         .unwrap();
-    assert_eq!(contribution, Uint128::new(100));
+    let contribution = contribution.unwrap();
+    assert_eq!(contribution.amount, Uint128::new(100));
+    assert_eq!(contribution.tier, Some("Bronze".to_string()));
+    assert_eq!(contribution.first_contribution, Timestamp::from_seconds(1));
 }
 
 #[test]
@@ -174,7 +381,10 @@ fn try_contribute_below_minimum() {
     let (mut router, _, kickstarter_addr, _, user, _) = setup_contracts();
 
     // Contribute to the campaign
-    let msg = crate::contract::sv::ExecMsg::Contribute {};
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
     let res = router.execute_contract(
         user.clone(),
         kickstarter_addr.clone(),
@@ -190,7 +400,10 @@ fn try_refund() {
     let (mut router, cw20_addr, kickstarter_addr, _, user, _) = setup_contracts();
 
     // Contribute to the campaign
-    let msg = crate::contract::sv::ExecMsg::Contribute {};
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
     router
         .execute_contract(
             user.clone(),
@@ -234,7 +447,10 @@ fn try_refund() {
         .wrap()
         .query_wasm_smart(
             kickstarter_addr.clone(),
-            &crate::contract::sv::QueryMsg::Contributions {},
+            &crate::contract::sv::QueryMsg::Contributions {
+                start_after: None,
+                limit: None,
+            },
         )
         .unwrap();
     assert_eq!(contributors.len(), 0);
@@ -260,7 +476,10 @@ pub fn try_refund_too_many_tokens() {
     let (mut router, cw20_addr, kickstarter_addr, _, user, _) = setup_contracts();
 
     // Contribute to the campaign
-    let msg = crate::contract::sv::ExecMsg::Contribute {};
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
     router
         .execute_contract(
             user.clone(),
@@ -298,7 +517,10 @@ pub fn try_end_campaign() {
     let (mut router, cw20_addr, kickstarter_addr, admin, user, fee) = setup_contracts();
 
     // Contribute to the campaign
-    let msg = crate::contract::sv::ExecMsg::Contribute {};
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
     router
         .execute_contract(
             user.clone(),
@@ -354,3 +576,3901 @@ pub fn try_end_campaign_before_end() {
 
     assert!(res.is_err());
 }
+
+#[test]
+pub fn try_end_campaign_twice() {
+    let (mut router, _, kickstarter_addr, admin, user, _) = setup_contracts();
+
+    // Contribute to the campaign
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(100, "ustars".to_string())],
+        )
+        .unwrap();
+
+    // Push time to after campaign end
+    add_block_time(&mut router, 86400);
+
+    // End the campaign
+    let msg = crate::contract::sv::ExecMsg::EndCampaign {};
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let campaign: crate::storage::Campaign = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Info {},
+        )
+        .unwrap();
+    assert!(campaign.finalized);
+
+    // Ending the campaign a second time is rejected
+    let res = router.execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+}
+
+#[test]
+pub fn try_refund_after_finalization() {
+    let (mut router, cw20_addr, kickstarter_addr, admin, user, _) = setup_contracts();
+
+    // Contribute to the campaign
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(100, "ustars".to_string())],
+        )
+        .unwrap();
+
+    // Push time to after campaign end and end the campaign
+    add_block_time(&mut router, 86400);
+    let msg = crate::contract::sv::ExecMsg::EndCampaign {};
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    // Refunding is no longer possible
+    let msg = cw20::Cw20ExecuteMsg::Send {
+        contract: kickstarter_addr.to_string(),
+        amount: Uint128::from(100u128),
+        msg: Binary::new(b"{}".to_vec()),
+    };
+    let res = router.execute_contract(user.clone(), cw20_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+}
+
+#[test]
+pub fn try_end_campaign_with_many_backers() {
+    let (mut router, _, kickstarter_addr, admin, _, fee) = setup_contracts();
+
+    const BACKERS: u128 = 25_000;
+
+    // Simulate backers by writing their contributions straight into storage
+    {
+        let contract = crate::contract::KickstarterContract::new();
+        let mut storage = router.contract_storage_mut(&kickstarter_addr);
+        for i in 0..BACKERS {
+            contract
+                .contributions
+                .save(
+                    storage.as_mut(),
+                    Addr::unchecked(format!("backer{}", i)),
+                    &crate::storage::Contribution {
+                        amount: Uint128::new(100),
+                        receipts: Uint128::new(100),
+                        tier: Some("Bronze".to_string()),
+                        first_contribution: Timestamp::from_seconds(1),
+                        last_contribution: Timestamp::from_seconds(1),
+                        nft_holder: false,
+                        settled: false,
+                    },
+                )
+                .unwrap();
+        }
+    }
+    router
+        .sudo(cw_multi_test::SudoMsg::Bank(
+            cw_multi_test::BankSudo::Mint {
+                to_address: kickstarter_addr.to_string(),
+                amount: coins(BACKERS * 100, "ustars"),
+            },
+        ))
+        .unwrap();
+
+    // Push time to after campaign end
+    add_block_time(&mut router, 86400);
+
+    // End the campaign
+    let msg = crate::contract::sv::ExecMsg::EndCampaign {};
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    // Ensure the fee account has received 5% of the raised funds
+    let fee_balance: Coin = router
+        .wrap()
+        .query_balance(fee, "ustars".to_string())
+        .unwrap();
+    assert_eq!(fee_balance.amount, Uint128::new(BACKERS * 5));
+
+    // Ensure the admin has received the rest
+    let admin_balance: Coin = router
+        .wrap()
+        .query_balance(admin, "ustars".to_string())
+        .unwrap();
+    assert_eq!(admin_balance.amount, Uint128::new(1000 + BACKERS * 95));
+}
+
+#[test]
+pub fn try_query_contributions_after_finalization() {
+    let (mut router, _, kickstarter_addr, admin, user, _) = setup_contracts();
+
+    // Contribute to the campaign twice from the user and once from the admin
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(200, "ustars".to_string())],
+        )
+        .unwrap();
+    add_block_time(&mut router, 60);
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(300, "ustars".to_string())],
+        )
+        .unwrap();
+    router
+        .execute_contract(
+            admin.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(100, "ustars".to_string())],
+        )
+        .unwrap();
+
+    // Push time to after campaign end and end the campaign
+    add_block_time(&mut router, 86400);
+    let msg = crate::contract::sv::ExecMsg::EndCampaign {};
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    // Ensure the user's record is retained with its tier and timestamps
+    let contribution: Option<ContributionResponse> = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Contribution {
+                address: user.to_string(),
+            },
+        )
+        .unwrap();
+    let contribution = contribution.unwrap();
+    assert_eq!(contribution.amount, Uint128::new(500));
+    assert_eq!(contribution.tier, Some("Silver".to_string()));
+    assert_eq!(contribution.first_contribution, Timestamp::from_seconds(1));
+    assert_eq!(contribution.last_contribution, Timestamp::from_seconds(61));
+    assert!(contribution.finalized);
+
+    // Ensure every backer is still listed, one page at a time
+    let first_page: Vec<ContributionResponse> = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Contributions {
+                start_after: None,
+                limit: Some(1),
+            },
+        )
+        .unwrap();
+    assert_eq!(first_page.len(), 1);
+
+    let second_page: Vec<ContributionResponse> = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Contributions {
+                start_after: Some(first_page[0].contributor.to_string()),
+                limit: Some(1),
+            },
+        )
+        .unwrap();
+    assert_eq!(second_page.len(), 1);
+    assert_ne!(first_page[0].contributor, second_page[0].contributor);
+    assert!(second_page[0].finalized);
+}
+
+#[test]
+pub fn try_query_history() {
+    let (mut router, cw20_addr, kickstarter_addr, admin, user, _) = setup_contracts();
+
+    // Contribute to the campaign from both accounts
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(200, "ustars".to_string())],
+        )
+        .unwrap();
+    add_block_time(&mut router, 60);
+    router
+        .execute_contract(
+            admin.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(100, "ustars".to_string())],
+        )
+        .unwrap();
+    add_block_time(&mut router, 60);
+
+    // Partially refund the user
+    let msg = cw20::Cw20ExecuteMsg::Send {
+        contract: kickstarter_addr.to_string(),
+        amount: Uint128::from(50u128),
+        msg: Binary::new(b"{}".to_vec()),
+    };
+    router
+        .execute_contract(user.clone(), cw20_addr.clone(), &msg, &[])
+        .unwrap();
+
+    // Ensure the user's history holds the contribution and the refund
+    let history: Vec<LedgerEntry> = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::HistoryByAddress {
+                address: user.to_string(),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].id, 0);
+    assert_eq!(history[0].kind, LedgerKind::Contribution);
+    assert_eq!(history[0].amount, Uint128::new(200));
+    assert_eq!(history[0].denom, "ustars");
+    assert_eq!(history[1].id, 2);
+    assert_eq!(history[1].kind, LedgerKind::Refund);
+    assert_eq!(history[1].amount, Uint128::new(50));
+    assert_eq!(history[1].timestamp, Timestamp::from_seconds(121));
+
+    // Ensure the history can be paginated by address
+    let history: Vec<LedgerEntry> = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::HistoryByAddress {
+                address: user.to_string(),
+                start_after: Some(0),
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].id, 2);
+
+    // Ensure the history can be filtered by time range
+    let history: Vec<LedgerEntry> = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::HistoryByTime {
+                start: Some(Timestamp::from_seconds(61)),
+                end: Some(Timestamp::from_seconds(121)),
+                start_after: None,
+                limit: Some(1),
+            },
+        )
+        .unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].address, admin);
+
+    let history: Vec<LedgerEntry> = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::HistoryByTime {
+                start: Some(Timestamp::from_seconds(61)),
+                end: Some(Timestamp::from_seconds(121)),
+                start_after: Some(history[0].id),
+                limit: Some(1),
+            },
+        )
+        .unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].kind, LedgerKind::Refund);
+
+    let history: Vec<LedgerEntry> = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::HistoryByTime {
+                start: None,
+                end: Some(Timestamp::from_seconds(60)),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].address, user);
+}
+
+#[test]
+pub fn try_contribute_with_memo() {
+    let (mut router, _, kickstarter_addr, admin, user, _) = setup_contracts();
+
+    // Contribute to the campaign with and without a memo
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: Some("Good luck!".to_string()),
+        allowlist: None,
+    };
+    let res = router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(100, "ustars".to_string())],
+        )
+        .unwrap();
+    assert!(res.events.iter().any(|event| event
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "memo" && attr.value == "Good luck!")));
+
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            admin.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(100, "ustars".to_string())],
+        )
+        .unwrap();
+
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: Some("Take my money".to_string()),
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            admin.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(200, "ustars".to_string())],
+        )
+        .unwrap();
+
+    // Ensure the latest messages are listed first
+    let messages: Vec<MessageResponse> = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Messages {
+                start_before: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[0].contributor, admin);
+    assert_eq!(messages[0].amount, Uint128::new(200));
+    assert_eq!(messages[0].memo, "Take my money");
+    assert_eq!(messages[1].contributor, user);
+    assert_eq!(messages[1].memo, "Good luck!");
+
+    // Ensure the messages can be paginated
+    let messages: Vec<MessageResponse> = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Messages {
+                start_before: Some(messages[0].id),
+                limit: Some(1),
+            },
+        )
+        .unwrap();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].memo, "Good luck!");
+
+    // Ensure the memo is stored in the ledger
+    let history: Vec<LedgerEntry> = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::HistoryByAddress {
+                address: user.to_string(),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(history[0].memo, Some("Good luck!".to_string()));
+}
+
+#[test]
+pub fn try_contribute_with_long_memo() {
+    let (mut router, _, kickstarter_addr, _, user, _) = setup_contracts();
+
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: Some("a".repeat(crate::contract::MAX_MEMO_LENGTH + 1)),
+        allowlist: None,
+    };
+    let res = router.execute_contract(
+        user.clone(),
+        kickstarter_addr.clone(),
+        &msg,
+        &[coin(100, "ustars".to_string())],
+    );
+    assert!(res.is_err());
+}
+
+#[test]
+pub fn try_contribute_above_hard_cap() {
+    let (mut router, cw20_addr, kickstarter_addr, admin, user, _) =
+        setup_contracts_with(crate::storage::CampaignMeta {
+            goal: Uint128::new(200),
+            max_raise: Some(Uint128::new(300)),
+            ..default_campaign()
+        });
+
+    // Contribute past the hard cap
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            admin.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(200, "ustars".to_string())],
+        )
+        .unwrap();
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(250, "ustars".to_string())],
+        )
+        .unwrap();
+
+    // Ensure only the amount below the cap was accepted
+    let contribution: Option<ContributionResponse> = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Contribution {
+                address: user.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(contribution.unwrap().amount, Uint128::new(100));
+
+    let user_balance: BalanceResponse = router
+        .wrap()
+        .query_wasm_smart(
+            cw20_addr.clone(),
+            &cw20::Cw20QueryMsg::Balance {
+                address: user.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(user_balance.balance, Uint128::new(100));
+
+    // Ensure the excess was sent back
+    let user_balance: Coin = router
+        .wrap()
+        .query_balance(user.clone(), "ustars".to_string())
+        .unwrap();
+    assert_eq!(user_balance.amount, Uint128::new(900));
+
+    // Ensure further contributions are rejected
+    let res = router.execute_contract(
+        user.clone(),
+        kickstarter_addr.clone(),
+        &msg,
+        &[coin(100, "ustars".to_string())],
+    );
+    assert!(res.is_err());
+}
+
+#[test]
+pub fn try_contribute_below_minimum_after_hard_cap() {
+    let (mut router, _, kickstarter_addr, admin, user, _) =
+        setup_contracts_with(crate::storage::CampaignMeta {
+            goal: Uint128::new(200),
+            max_raise: Some(Uint128::new(300)),
+            ..default_campaign()
+        });
+
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            admin.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(250, "ustars".to_string())],
+        )
+        .unwrap();
+
+    // Only 50 fit under the cap, which is below the minimum of 100
+    let err = router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(200, "ustars".to_string())],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Generic error: Contribution below the hard cap is lower than the minimum"
+    );
+
+    let user_balance: Coin = router
+        .wrap()
+        .query_balance(user.clone(), "ustars".to_string())
+        .unwrap();
+    assert_eq!(user_balance.amount, Uint128::new(1000));
+}
+
+#[test]
+pub fn try_contribute_above_address_limit() {
+    let (mut router, _, kickstarter_addr, _, user, _) =
+        setup_contracts_with(crate::storage::CampaignMeta {
+            max_contribution_per_address: Some(Uint128::new(300)),
+            ..default_campaign()
+        });
+
+    // Contribute up to the per-address limit
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(200, "ustars".to_string())],
+        )
+        .unwrap();
+
+    // Ensure going over the limit is rejected
+    let res = router.execute_contract(
+        user.clone(),
+        kickstarter_addr.clone(),
+        &msg,
+        &[coin(101, "ustars".to_string())],
+    );
+    assert!(res.is_err());
+
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(100, "ustars".to_string())],
+        )
+        .unwrap();
+}
+
+#[test]
+#[should_panic(expected = "Hard cap cannot be lower than the goal")]
+pub fn try_instantiate_with_hard_cap_below_goal() {
+    setup_contracts_with(crate::storage::CampaignMeta {
+        max_raise: Some(Uint128::new(5000)),
+        ..default_campaign()
+    });
+}
+
+#[test]
+#[should_panic(expected = "Per-address limit cannot be lower than the minimum contribution")]
+pub fn try_instantiate_with_address_limit_below_minimum() {
+    setup_contracts_with(crate::storage::CampaignMeta {
+        max_contribution_per_address: Some(Uint128::new(50)),
+        ..default_campaign()
+    });
+}
+
+#[test]
+pub fn try_contribute_before_start() {
+    let (mut router, _, kickstarter_addr, _, user, _) =
+        setup_contracts_with(crate::storage::CampaignMeta {
+            start_time: Some(Timestamp::from_seconds(3600)),
+            ..default_campaign()
+        });
+
+    // Contributing during pre-launch is rejected
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    let res = router.execute_contract(
+        user.clone(),
+        kickstarter_addr.clone(),
+        &msg,
+        &[coin(100, "ustars".to_string())],
+    );
+    assert!(res.is_err());
+
+    // Contributing once the campaign has started is accepted
+    add_block_time(&mut router, 3600);
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(100, "ustars".to_string())],
+        )
+        .unwrap();
+}
+
+#[test]
+pub fn try_update_campaign_during_pre_launch() {
+    let (mut router, _, kickstarter_addr, admin, _, _) =
+        setup_contracts_with(crate::storage::CampaignMeta {
+            start_time: Some(Timestamp::from_seconds(3600)),
+            ..default_campaign()
+        });
+
+    // Update the name, goal and tiers before the campaign starts
+    let msg = crate::contract::sv::ExecMsg::UpdateCampaign {
+        description: "New Description".to_string(),
+        links: vec![],
+        minimum_contribution: None,
+        name: Some("New Name".to_string()),
+        goal: Some(Uint128::new(20000)),
+        tiers: Some(vec![]),
+        end_time: None,
+    };
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let campaign: crate::storage::Campaign = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Info {},
+        )
+        .unwrap();
+    assert_eq!(campaign.name, "New Name");
+    assert_eq!(campaign.goal, Uint128::new(20000));
+    assert!(campaign.tiers.is_empty());
+
+    // The same update is rejected once the campaign has started
+    add_block_time(&mut router, 3600);
+    let res = router.execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    // Description and links can still be updated
+    let msg = crate::contract::sv::ExecMsg::UpdateCampaign {
+        description: "Newer Description".to_string(),
+        links: vec![],
+        minimum_contribution: None,
+        name: None,
+        goal: None,
+        tiers: None,
+        end_time: None,
+    };
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+}
+
+#[test]
+pub fn try_add_tier_after_contribution() {
+    let (mut router, _, kickstarter_addr, admin, user, _) = setup_contracts();
+
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(400, "ustars".to_string())],
+        )
+        .unwrap();
+
+    // A new tier is added below what the user contributed
+    let mut tiers = default_campaign().tiers;
+    tiers.push(crate::storage::Tier {
+        name: "Gold".to_string(),
+        description: "Gold Tier".to_string(),
+        required_contribution: Uint128::new(300),
+    });
+    let msg = crate::contract::sv::ExecMsg::UpdateCampaign {
+        description: "My Campaign Description".to_string(),
+        links: vec![],
+        minimum_contribution: None,
+        name: None,
+        goal: None,
+        tiers: Some(tiers),
+        end_time: None,
+    };
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    // The user now qualifies for it
+    let contribution: Option<ContributionResponse> = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Contribution {
+                address: user.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(contribution.unwrap().tier, Some("Gold".to_string()));
+
+    let contributions: Vec<ContributionResponse> = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Contributions {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(contributions[0].tier, Some("Gold".to_string()));
+}
+
+#[test]
+pub fn try_update_campaign_before_contributions() {
+    let (mut router, _, kickstarter_addr, admin, user, _) =
+        setup_contracts_with(crate::storage::CampaignMeta {
+            start_time: Some(Timestamp::from_seconds(3600)),
+            ..default_campaign()
+        });
+
+    // Update the name, goal and tiers before anyone contributes
+    let msg = crate::contract::sv::ExecMsg::UpdateCampaign {
+        description: "New Description".to_string(),
+        links: vec![],
+        minimum_contribution: None,
+        name: Some("New Name".to_string()),
+        goal: Some(Uint128::new(20000)),
+        tiers: Some(vec![]),
+        end_time: None,
+    };
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let campaign: crate::storage::Campaign = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Info {},
+        )
+        .unwrap();
+    assert_eq!(campaign.name, "New Name");
+    assert_eq!(campaign.goal, Uint128::new(20000));
+    assert!(campaign.tiers.is_empty());
+
+    // Once someone contributed, the goal can no longer change
+    add_block_time(&mut router, 3600);
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(200, "ustars".to_string())],
+        )
+        .unwrap();
+
+    let msg = crate::contract::sv::ExecMsg::UpdateCampaign {
+        description: "New Description".to_string(),
+        links: vec![],
+        minimum_contribution: None,
+        name: None,
+        goal: Some(Uint128::new(30000)),
+        tiers: None,
+        end_time: None,
+    };
+    let res = router.execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    // But the name still can
+    let msg = crate::contract::sv::ExecMsg::UpdateCampaign {
+        description: "New Description".to_string(),
+        links: vec![],
+        minimum_contribution: None,
+        name: Some("Newer Name".to_string()),
+        goal: None,
+        tiers: None,
+        end_time: None,
+    };
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    // Description and links can still be updated
+    let msg = crate::contract::sv::ExecMsg::UpdateCampaign {
+        description: "Newer Description".to_string(),
+        links: vec![],
+        minimum_contribution: None,
+        name: None,
+        goal: None,
+        tiers: None,
+        end_time: None,
+    };
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+}
+
+#[test]
+pub fn try_update_campaign_commitments() {
+    let (mut router, _, kickstarter_addr, admin, user, _) = setup_contracts();
+
+    // The end time can move freely before anyone contributes
+    let msg = crate::contract::sv::ExecMsg::UpdateCampaign {
+        description: "My Campaign Description".to_string(),
+        links: vec![],
+        minimum_contribution: None,
+        name: None,
+        goal: None,
+        tiers: None,
+        end_time: Some(Timestamp::from_seconds(43200)),
+    };
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    // Contribute enough for the Bronze tier only
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(200, "ustars".to_string())],
+        )
+        .unwrap();
+
+    let bronze = crate::storage::Tier {
+        name: "Bronze".to_string(),
+        description: "Bronze Tier".to_string(),
+        required_contribution: Uint128::new(100),
+    };
+    let gold = crate::storage::Tier {
+        name: "Gold".to_string(),
+        description: "Gold Tier".to_string(),
+        required_contribution: Uint128::new(1000),
+    };
+    let update_tiers = |tiers| crate::contract::sv::ExecMsg::UpdateCampaign {
+        description: "My Campaign Description".to_string(),
+        links: vec![],
+        minimum_contribution: None,
+        name: None,
+        goal: None,
+        tiers: Some(tiers),
+        end_time: None,
+    };
+
+    // Bronze cannot be removed or repriced
+    let msg = update_tiers(vec![gold.clone()]);
+    let err = router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap_err();
+    assert!(err.root_cause().to_string().contains("Tier Bronze"));
+
+    let msg = update_tiers(vec![crate::storage::Tier {
+        required_contribution: Uint128::new(150),
+        ..bronze.clone()
+    }]);
+    let res = router.execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    // Silver has no backers yet, so it can be replaced by Gold
+    let msg = update_tiers(vec![bronze.clone(), gold.clone()]);
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    // The end time can no longer be shortened
+    let msg = crate::contract::sv::ExecMsg::UpdateCampaign {
+        description: "My Campaign Description".to_string(),
+        links: vec![],
+        minimum_contribution: None,
+        name: None,
+        goal: None,
+        tiers: None,
+        end_time: Some(Timestamp::from_seconds(7200)),
+    };
+    let err = router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Generic error: End time cannot be shortened once contributions have been made"
+    );
+
+    // Every version of the campaign is kept
+    let history: Vec<crate::storage::CampaignRevision> = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::CampaignHistory {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(history.len(), 3);
+    assert!(history[0].changes.is_empty());
+    assert_eq!(history[0].campaign.end_time, Timestamp::from_seconds(86400));
+    assert_eq!(history[1].changes, vec!["end_time".to_string()]);
+    assert_eq!(history[2].version, 2);
+    assert_eq!(history[2].editor, admin);
+    assert_eq!(history[2].changes, vec!["tiers".to_string()]);
+    assert_eq!(history[2].campaign.tiers, vec![bronze, gold]);
+}
+
+#[test]
+#[should_panic(expected = "Campaign cannot last longer than a year")]
+pub fn try_instantiate_longer_than_a_year() {
+    // The one-year limit is measured from the start time
+    setup_contracts_with(crate::storage::CampaignMeta {
+        start_time: Some(Timestamp::from_seconds(3600)),
+        end_time: Timestamp::from_seconds(3601 + crate::contract::MAX_CAMPAIGN_DURATION),
+        ..default_campaign()
+    });
+}
+
+#[test]
+pub fn try_contribute_during_presale() {
+    let (mut router, _, kickstarter_addr, admin, user, _) =
+        setup_contracts_with(crate::storage::CampaignMeta {
+            start_time: Some(Timestamp::from_seconds(7200)),
+            presale: Some(crate::storage::Presale {
+                start_time: Timestamp::from_seconds(3600),
+                merkle_root: hex::encode([0u8; 32]),
+            }),
+            ..default_campaign()
+        });
+
+    // Allowlist the user with an allowance of 300 and the admin with 200
+    let user_leaf = allowlist_leaf(&user, 300);
+    let admin_leaf = allowlist_leaf(&admin, 200);
+    let merkle_root = allowlist_node(user_leaf, admin_leaf);
+
+    let msg = crate::contract::sv::ExecMsg::UpdateMerkleRoot {
+        merkle_root: hex::encode(merkle_root),
+    };
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let valid_proof = AllowlistProof {
+        allowance: Uint128::new(300),
+        proof: vec![hex::encode(admin_leaf)],
+    };
+
+    // Contributing before the presale opens is rejected
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: Some(valid_proof.clone()),
+    };
+    let res = router.execute_contract(
+        user.clone(),
+        kickstarter_addr.clone(),
+        &msg,
+        &[coin(100, "ustars".to_string())],
+    );
+    assert!(res.is_err());
+
+    // Contributing with a valid proof during the presale is accepted
+    add_block_time(&mut router, 3600);
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(200, "ustars".to_string())],
+        )
+        .unwrap();
+
+    // Going over the allowance is rejected
+    let res = router.execute_contract(
+        user.clone(),
+        kickstarter_addr.clone(),
+        &msg,
+        &[coin(101, "ustars".to_string())],
+    );
+    assert!(res.is_err());
+
+    // Once the campaign starts, the allowance no longer applies
+    add_block_time(&mut router, 3600);
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(200, "ustars".to_string())],
+        )
+        .unwrap();
+}
+
+#[test]
+pub fn try_contribute_during_presale_with_invalid_proof() {
+    let (mut router, _, kickstarter_addr, admin, user, _) =
+        setup_contracts_with(crate::storage::CampaignMeta {
+            start_time: Some(Timestamp::from_seconds(7200)),
+            presale: Some(crate::storage::Presale {
+                start_time: Timestamp::from_seconds(3600),
+                merkle_root: hex::encode([0u8; 32]),
+            }),
+            ..default_campaign()
+        });
+
+    // Only allowlist the admin
+    let admin_leaf = allowlist_leaf(&admin, 200);
+    let other_leaf = allowlist_leaf(&Addr::unchecked("other"), 200);
+    let merkle_root = allowlist_node(admin_leaf, other_leaf);
+
+    let msg = crate::contract::sv::ExecMsg::UpdateMerkleRoot {
+        merkle_root: hex::encode(merkle_root),
+    };
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    add_block_time(&mut router, 3600);
+
+    // Contributing without a proof is rejected
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    let res = router.execute_contract(
+        user.clone(),
+        kickstarter_addr.clone(),
+        &msg,
+        &[coin(100, "ustars".to_string())],
+    );
+    assert!(res.is_err());
+
+    // Reusing another address' proof is rejected
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: Some(AllowlistProof {
+            allowance: Uint128::new(200),
+            proof: vec![hex::encode(other_leaf)],
+        }),
+    };
+    let res = router.execute_contract(
+        user.clone(),
+        kickstarter_addr.clone(),
+        &msg,
+        &[coin(100, "ustars".to_string())],
+    );
+    assert!(res.is_err());
+
+    // Claiming a larger allowance than the one committed to is rejected
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: Some(AllowlistProof {
+            allowance: Uint128::new(1000),
+            proof: vec![hex::encode(other_leaf)],
+        }),
+    };
+    let res = router.execute_contract(
+        admin.clone(),
+        kickstarter_addr.clone(),
+        &msg,
+        &[coin(100, "ustars".to_string())],
+    );
+    assert!(res.is_err());
+
+    // The Merkle root can no longer be updated once the presale has started
+    let msg = crate::contract::sv::ExecMsg::UpdateMerkleRoot {
+        merkle_root: hex::encode([1u8; 32]),
+    };
+    let res = router.execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+}
+
+// Set up a campaign gated to holders of a mock NFT collection owned by the admin
+fn setup_gated_campaign(holder_tier: Option<String>) -> (App, Addr, Addr, Addr) {
+    let (mut router, admin, user, _) = setup_app();
+
+    let cw721_id = router.store_code(contract_cw721());
+    let collection = router
+        .instantiate_contract(cw721_id, admin.clone(), &Empty {}, &[], "NFT", None)
+        .unwrap();
+    let msg = mock_cw721::ExecuteMsg::Mint {
+        token_id: "1".to_string(),
+        owner: admin.to_string(),
+    };
+    router
+        .execute_contract(admin.clone(), collection.clone(), &msg, &[])
+        .unwrap();
+
+    let (_, kickstarter_addr) = setup_kickstarter(
+        &mut router,
+        &admin,
+        crate::storage::CampaignMeta {
+            nft_gate: Some(crate::storage::NftGate {
+                collections: vec![collection.to_string()],
+                end_time: Timestamp::from_seconds(3600),
+                holder_tier,
+            }),
+            ..default_campaign()
+        },
+        None,
+    );
+
+    (router, kickstarter_addr, admin, user)
+}
+
+#[test]
+pub fn try_contribute_during_nft_gate() {
+    let (mut router, kickstarter_addr, admin, user) = setup_gated_campaign(None);
+
+    // Contributing without holding an NFT is rejected during the gated window
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    let res = router.execute_contract(
+        user.clone(),
+        kickstarter_addr.clone(),
+        &msg,
+        &[coin(100, "ustars".to_string())],
+    );
+    assert!(res.is_err());
+
+    // Holders can contribute during the gated window
+    router
+        .execute_contract(
+            admin.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(100, "ustars".to_string())],
+        )
+        .unwrap();
+
+    // Everyone can contribute once the gated window is over
+    add_block_time(&mut router, 3600);
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(100, "ustars".to_string())],
+        )
+        .unwrap();
+}
+
+#[test]
+pub fn try_contribute_with_holder_tier() {
+    let (mut router, kickstarter_addr, admin, user) =
+        setup_gated_campaign(Some("Silver".to_string()));
+
+    // Holders reach the holder-only tier
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            admin.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(500, "ustars".to_string())],
+        )
+        .unwrap();
+
+    let contribution: Option<ContributionResponse> = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Contribution {
+                address: admin.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(contribution.unwrap().tier, Some("Silver".to_string()));
+
+    // Other backers do not
+    add_block_time(&mut router, 3600);
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(500, "ustars".to_string())],
+        )
+        .unwrap();
+
+    let contribution: Option<ContributionResponse> = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Contribution {
+                address: user.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(contribution.unwrap().tier, Some("Bronze".to_string()));
+}
+
+// Query the CW20 balance of an address
+fn cw20_balance(router: &App, cw20_addr: &Addr, address: &Addr) -> Uint128 {
+    let balance: BalanceResponse = router
+        .wrap()
+        .query_wasm_smart(
+            cw20_addr.clone(),
+            &cw20::Cw20QueryMsg::Balance {
+                address: address.to_string(),
+            },
+        )
+        .unwrap();
+    balance.balance
+}
+
+#[test]
+pub fn try_contribute_with_early_bird_bonus() {
+    let (mut router, cw20_addr, kickstarter_addr, _, user, _) =
+        setup_contracts_with(crate::storage::CampaignMeta {
+            bonus_schedule: vec![
+                crate::storage::BonusPeriod {
+                    duration: 48 * 3600,
+                    bonus: Decimal::percent(20),
+                },
+                crate::storage::BonusPeriod {
+                    duration: 7 * 24 * 3600,
+                    bonus: Decimal::percent(10),
+                },
+            ],
+            end_time: Timestamp::from_seconds(30 * 24 * 3600),
+            ..default_campaign()
+        });
+
+    // Contribute during the first 48 hours
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(100, "ustars".to_string())],
+        )
+        .unwrap();
+    assert_eq!(cw20_balance(&router, &cw20_addr, &user), Uint128::new(120));
+
+    // Contribute during the first week
+    add_block_time(&mut router, 48 * 3600);
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(100, "ustars".to_string())],
+        )
+        .unwrap();
+    assert_eq!(cw20_balance(&router, &cw20_addr, &user), Uint128::new(230));
+
+    // Contribute after the bonus periods
+    add_block_time(&mut router, 7 * 24 * 3600);
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(100, "ustars".to_string())],
+        )
+        .unwrap();
+    assert_eq!(cw20_balance(&router, &cw20_addr, &user), Uint128::new(330));
+
+    let contribution: Option<ContributionResponse> = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Contribution {
+                address: user.to_string(),
+            },
+        )
+        .unwrap();
+    let contribution = contribution.unwrap();
+    assert_eq!(contribution.amount, Uint128::new(300));
+    assert_eq!(contribution.receipts, Uint128::new(330));
+}
+
+#[test]
+pub fn try_refund_with_early_bird_bonus() {
+    let (mut router, cw20_addr, kickstarter_addr, _, user, _) =
+        setup_contracts_with(crate::storage::CampaignMeta {
+            bonus_schedule: vec![crate::storage::BonusPeriod {
+                duration: 48 * 3600,
+                bonus: Decimal::percent(20),
+            }],
+            ..default_campaign()
+        });
+
+    // Contribute during the bonus period
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(200, "ustars".to_string())],
+        )
+        .unwrap();
+
+    // Refund half of the receipts
+    let msg = cw20::Cw20ExecuteMsg::Send {
+        contract: kickstarter_addr.to_string(),
+        amount: Uint128::from(120u128),
+        msg: Binary::new(b"{}".to_vec()),
+    };
+    router
+        .execute_contract(user.clone(), cw20_addr.clone(), &msg, &[])
+        .unwrap();
+
+    // Ensure only the underlying amount was refunded and the receipts burned
+    let user_balance: Coin = router
+        .wrap()
+        .query_balance(user.clone(), "ustars".to_string())
+        .unwrap();
+    assert_eq!(user_balance.amount, Uint128::new(900));
+    assert_eq!(cw20_balance(&router, &cw20_addr, &user), Uint128::new(120));
+
+    // Refund the rest
+    router
+        .execute_contract(user.clone(), cw20_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let user_balance: Coin = router
+        .wrap()
+        .query_balance(user.clone(), "ustars".to_string())
+        .unwrap();
+    assert_eq!(user_balance.amount, Uint128::new(1000));
+    assert_eq!(cw20_balance(&router, &cw20_addr, &user), Uint128::zero());
+}
+
+// Set up a campaign minting receipts at the given exchange rate
+fn setup_contracts_with_rate(exchange_rate: Decimal) -> (App, Addr, Addr, Addr) {
+    let (mut router, admin, user, _) = setup_app();
+    let (cw20_addr, kickstarter_addr) =
+        setup_kickstarter(&mut router, &admin, default_campaign(), Some(exchange_rate));
+
+    (router, cw20_addr, kickstarter_addr, user)
+}
+
+// Refund receipts and return the amount of ustars sent back
+fn refund_receipts(
+    router: &mut App,
+    cw20_addr: &Addr,
+    kickstarter_addr: &Addr,
+    user: &Addr,
+    amount: u128,
+) -> Uint128 {
+    let before = router
+        .wrap()
+        .query_balance(user.clone(), "ustars".to_string())
+        .unwrap();
+
+    let msg = cw20::Cw20ExecuteMsg::Send {
+        contract: kickstarter_addr.to_string(),
+        amount: Uint128::new(amount),
+        msg: Binary::new(b"{}".to_vec()),
+    };
+    router
+        .execute_contract(user.clone(), cw20_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let after = router
+        .wrap()
+        .query_balance(user.clone(), "ustars".to_string())
+        .unwrap();
+    after.amount - before.amount
+}
+
+#[test]
+pub fn try_contribute_with_exchange_rate() {
+    let (mut router, cw20_addr, kickstarter_addr, user) =
+        setup_contracts_with_rate(Decimal::percent(10000));
+
+    let config: crate::msg::ConfigResponse = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Config {},
+        )
+        .unwrap();
+    assert_eq!(config.exchange_rate, Decimal::percent(10000));
+
+    // Contribute at 100 receipts per ustars
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(101, "ustars".to_string())],
+        )
+        .unwrap();
+    assert_eq!(
+        cw20_balance(&router, &cw20_addr, &user),
+        Uint128::new(10100)
+    );
+
+    // Refunding less receipts than a single ustars is rejected
+    let msg = cw20::Cw20ExecuteMsg::Send {
+        contract: kickstarter_addr.to_string(),
+        amount: Uint128::new(99),
+        msg: Binary::new(b"{}".to_vec()),
+    };
+    let res = router.execute_contract(user.clone(), cw20_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    // Partial refunds round down
+    let refunded = refund_receipts(&mut router, &cw20_addr, &kickstarter_addr, &user, 150);
+    assert_eq!(refunded, Uint128::new(1));
+
+    // Returning every receipt refunds the rest, leaving no dust behind
+    let refunded = refund_receipts(&mut router, &cw20_addr, &kickstarter_addr, &user, 9950);
+    assert_eq!(refunded, Uint128::new(100));
+    assert_eq!(cw20_balance(&router, &cw20_addr, &user), Uint128::zero());
+}
+
+#[test]
+pub fn try_contribute_with_fractional_exchange_rate() {
+    let (mut router, cw20_addr, kickstarter_addr, user) =
+        setup_contracts_with_rate(Decimal::percent(30));
+
+    // Receipts are rounded down
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(101, "ustars".to_string())],
+        )
+        .unwrap();
+    assert_eq!(cw20_balance(&router, &cw20_addr, &user), Uint128::new(30));
+
+    // Partial refunds return the matching share of the contribution
+    let refunded = refund_receipts(&mut router, &cw20_addr, &kickstarter_addr, &user, 10);
+    assert_eq!(refunded, Uint128::new(33));
+
+    // Returning every receipt refunds the rest
+    let refunded = refund_receipts(&mut router, &cw20_addr, &kickstarter_addr, &user, 20);
+    assert_eq!(refunded, Uint128::new(68));
+}
+
+#[test]
+pub fn try_contribute_below_one_receipt() {
+    let (mut router, _, kickstarter_addr, user) = setup_contracts_with_rate(Decimal::permille(1));
+
+    // Contributions worth less than a single receipt are rejected
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    let res = router.execute_contract(
+        user.clone(),
+        kickstarter_addr.clone(),
+        &msg,
+        &[coin(100, "ustars".to_string())],
+    );
+    assert!(res.is_err());
+}
+
+// Contribute from both accounts and finalize the campaign
+fn setup_finalized_campaign() -> (App, Addr, Addr, Addr, Addr) {
+    let (mut router, cw20_addr, kickstarter_addr, admin, user, _) = setup_contracts();
+
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(300, "ustars".to_string())],
+        )
+        .unwrap();
+    router
+        .execute_contract(
+            admin.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(100, "ustars".to_string())],
+        )
+        .unwrap();
+
+    add_block_time(&mut router, 86400);
+    let msg = crate::contract::sv::ExecMsg::EndCampaign {};
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    (router, cw20_addr, kickstarter_addr, admin, user)
+}
+
+#[test]
+pub fn try_claim_tokens() {
+    let (mut router, cw20_addr, kickstarter_addr, admin, user) = setup_finalized_campaign();
+
+    router
+        .sudo(cw_multi_test::SudoMsg::Bank(
+            cw_multi_test::BankSudo::Mint {
+                to_address: admin.to_string(),
+                amount: coins(1000, "uproject"),
+            },
+        ))
+        .unwrap();
+
+    // Only the creator can deposit project tokens
+    let msg = crate::contract::sv::ExecMsg::DepositTokens {
+        claim_deadline: Some(Timestamp::from_seconds(200000)),
+        vesting: None,
+    };
+    let res = router.execute_contract(
+        user.clone(),
+        kickstarter_addr.clone(),
+        &msg,
+        &[coin(100, "ustars".to_string())],
+    );
+    assert!(res.is_err());
+
+    router
+        .execute_contract(
+            admin.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(1000, "uproject".to_string())],
+        )
+        .unwrap();
+
+    // Ensure the tokens are claimable pro-rata to contributions
+    let claimable: Uint128 = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::ClaimableTokens {
+                address: user.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(claimable, Uint128::new(750));
+
+    let msg = crate::contract::sv::ExecMsg::ClaimTokens {};
+    router
+        .execute_contract(user.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let user_balance: Coin = router
+        .wrap()
+        .query_balance(user.clone(), "uproject".to_string())
+        .unwrap();
+    assert_eq!(user_balance.amount, Uint128::new(750));
+
+    let admin_balance: Coin = router
+        .wrap()
+        .query_balance(admin.clone(), "uproject".to_string())
+        .unwrap();
+    assert_eq!(admin_balance.amount, Uint128::new(250));
+
+    // Ensure tokens cannot be claimed twice
+    let res = router.execute_contract(user.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    // Receipt holders without a contribution have nothing to claim
+    let other = router.api().addr_make("other");
+    let msg = cw20::Cw20ExecuteMsg::Transfer {
+        recipient: other.to_string(),
+        amount: Uint128::new(100),
+    };
+    router
+        .execute_contract(user.clone(), cw20_addr.clone(), &msg, &[])
+        .unwrap();
+    let msg = crate::contract::sv::ExecMsg::ClaimTokens {};
+    let res = router.execute_contract(other, kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+}
+
+#[test]
+pub fn try_claim_cw20_tokens_and_withdraw_unclaimed() {
+    let (mut router, _, kickstarter_addr, admin, user) = setup_finalized_campaign();
+
+    // Set up the project's CW20 token
+    let cw20_id = router.store_code(contract_cw20());
+    let msg = cw20_base::msg::InstantiateMsg {
+        name: String::from("Project Token"),
+        symbol: String::from("PRJ"),
+        decimals: 6,
+        initial_balances: vec![cw20::Cw20Coin {
+            address: admin.to_string(),
+            amount: Uint128::new(2000),
+        }],
+        mint: None,
+        marketing: None,
+    };
+    let project_addr = router
+        .instantiate_contract(cw20_id, admin.clone(), &msg, &[], "PRJ_CW20", None)
+        .unwrap();
+
+    // Project tokens are rejected until the creator allows them
+    let msg = cw20::Cw20ExecuteMsg::Send {
+        contract: kickstarter_addr.to_string(),
+        amount: Uint128::new(2000),
+        msg: cosmwasm_std::to_json_binary(&crate::msg::ReceiveMsg::DepositTokens {
+            claim_deadline: Some(Timestamp::from_seconds(200000)),
+            vesting: None,
+        })
+        .unwrap(),
+    };
+    let res = router.execute_contract(admin.clone(), project_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    let msg = crate::contract::sv::ExecMsg::AllowDistributionToken {
+        address: project_addr.to_string(),
+    };
+    let res = router.execute_contract(user.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    // Deposit the project tokens
+    let msg = cw20::Cw20ExecuteMsg::Send {
+        contract: kickstarter_addr.to_string(),
+        amount: Uint128::new(2000),
+        msg: cosmwasm_std::to_json_binary(&crate::msg::ReceiveMsg::DepositTokens {
+            claim_deadline: Some(Timestamp::from_seconds(200000)),
+            vesting: None,
+        })
+        .unwrap(),
+    };
+    router
+        .execute_contract(admin.clone(), project_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let distribution: Option<crate::storage::Distribution> = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Distribution {},
+        )
+        .unwrap();
+    let distribution = distribution.unwrap();
+    assert_eq!(
+        distribution.token,
+        crate::storage::Token::Cw20 {
+            address: project_addr.clone()
+        }
+    );
+    assert_eq!(distribution.total, Uint128::new(2000));
+
+    // The user claims their share
+    let msg = crate::contract::sv::ExecMsg::ClaimTokens {};
+    router
+        .execute_contract(user.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+    assert_eq!(
+        cw20_balance(&router, &project_addr, &user),
+        Uint128::new(1500)
+    );
+
+    // Unclaimed tokens cannot be withdrawn before the deadline
+    let msg = crate::contract::sv::ExecMsg::WithdrawUnclaimedTokens {};
+    let res = router.execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    // After the deadline, claims are closed and the creator gets the rest back
+    add_block_time(&mut router, 200000);
+    let msg = crate::contract::sv::ExecMsg::ClaimTokens {};
+    let res = router.execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    let msg = crate::contract::sv::ExecMsg::WithdrawUnclaimedTokens {};
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+    assert_eq!(
+        cw20_balance(&router, &project_addr, &admin),
+        Uint128::new(500)
+    );
+}
+
+#[test]
+pub fn try_deposit_tokens_with_forged_receive() {
+    let (mut router, _, kickstarter_addr, admin, user) = setup_finalized_campaign();
+
+    // A wallet calls the CW20 hook directly, pretending to be the creator
+    let msg = crate::contract::sv::ExecMsg::Receive {
+        sender: admin.to_string(),
+        amount: Uint128::new(1000000),
+        msg: cosmwasm_std::to_json_binary(&crate::msg::ReceiveMsg::DepositTokens {
+            claim_deadline: Some(Timestamp::from_seconds(4_000_000_000)),
+            vesting: None,
+        })
+        .unwrap(),
+    };
+    let err = router
+        .execute_contract(user.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Generic error: Token has not been allowed for distribution"
+    );
+
+    // Allowing a token does not let anyone else deposit in its name
+    let msg = crate::contract::sv::ExecMsg::AllowDistributionToken {
+        address: router.api().addr_make("project").to_string(),
+    };
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let msg = crate::contract::sv::ExecMsg::Receive {
+        sender: admin.to_string(),
+        amount: Uint128::new(1000000),
+        msg: cosmwasm_std::to_json_binary(&crate::msg::ReceiveMsg::DepositTokens {
+            claim_deadline: Some(Timestamp::from_seconds(4_000_000_000)),
+            vesting: None,
+        })
+        .unwrap(),
+    };
+    let res = router.execute_contract(user.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    let distribution: Option<crate::storage::Distribution> = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Distribution {},
+        )
+        .unwrap();
+    assert!(distribution.is_none());
+}
+
+#[test]
+pub fn try_deposit_tokens_before_finalization() {
+    let (mut router, _, kickstarter_addr, admin, _, _) = setup_contracts();
+
+    router
+        .sudo(cw_multi_test::SudoMsg::Bank(
+            cw_multi_test::BankSudo::Mint {
+                to_address: admin.to_string(),
+                amount: coins(1000, "uproject"),
+            },
+        ))
+        .unwrap();
+
+    let msg = crate::contract::sv::ExecMsg::DepositTokens {
+        claim_deadline: Some(Timestamp::from_seconds(200000)),
+        vesting: None,
+    };
+    let res = router.execute_contract(
+        admin.clone(),
+        kickstarter_addr.clone(),
+        &msg,
+        &[coin(1000, "uproject".to_string())],
+    );
+    assert!(res.is_err());
+}
+
+#[test]
+pub fn try_receive_without_receipt_tokens() {
+    let (mut router, _, kickstarter_addr, _, user, _) = setup_contracts();
+
+    // Contribute to the campaign
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(100, "ustars".to_string())],
+        )
+        .unwrap();
+
+    // Calling the receive hook directly does not refund anything
+    let msg = crate::contract::sv::ExecMsg::Receive {
+        sender: user.to_string(),
+        amount: Uint128::new(100),
+        msg: Binary::new(b"{}".to_vec()),
+    };
+    let res = router.execute_contract(user.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+}
+
+#[test]
+pub fn try_claim_vested_tokens() {
+    let (mut router, _, kickstarter_addr, admin, user) = setup_finalized_campaign();
+
+    router
+        .sudo(cw_multi_test::SudoMsg::Bank(
+            cw_multi_test::BankSudo::Mint {
+                to_address: admin.to_string(),
+                amount: coins(1000, "uproject"),
+            },
+        ))
+        .unwrap();
+
+    let start_time = router.block_info().time;
+
+    // A cliff longer than the vesting duration is rejected
+    let msg = crate::contract::sv::ExecMsg::DepositTokens {
+        claim_deadline: Some(Timestamp::from_seconds(200000)),
+        vesting: Some(crate::storage::VestingSchedule {
+            start_time,
+            cliff: 20000,
+            duration: 10000,
+        }),
+    };
+    let res = router.execute_contract(
+        admin.clone(),
+        kickstarter_addr.clone(),
+        &msg,
+        &[coin(1000, "uproject".to_string())],
+    );
+    assert!(res.is_err());
+
+    // Deposit tokens vesting linearly over 10000 seconds after a 1000 second cliff
+    let msg = crate::contract::sv::ExecMsg::DepositTokens {
+        claim_deadline: Some(Timestamp::from_seconds(200000)),
+        vesting: Some(crate::storage::VestingSchedule {
+            start_time,
+            cliff: 1000,
+            duration: 10000,
+        }),
+    };
+    router
+        .execute_contract(
+            admin.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(1000, "uproject".to_string())],
+        )
+        .unwrap();
+
+    // Nothing can be claimed before the cliff
+    add_block_time(&mut router, 500);
+    let vested: VestedResponse = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Vested {
+                address: user.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(vested.total, Uint128::new(750));
+    assert_eq!(vested.claimable, Uint128::zero());
+    assert_eq!(vested.locked, Uint128::new(750));
+
+    let msg = crate::contract::sv::ExecMsg::ClaimTokens {};
+    let res = router.execute_contract(user.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    // Halfway through, half of the tokens can be claimed
+    add_block_time(&mut router, 4500);
+    router
+        .execute_contract(user.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let user_balance: Coin = router
+        .wrap()
+        .query_balance(user.clone(), "uproject".to_string())
+        .unwrap();
+    assert_eq!(user_balance.amount, Uint128::new(375));
+
+    // Once fully vested, the rest can be claimed
+    add_block_time(&mut router, 5000);
+    let vested: VestedResponse = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Vested {
+                address: user.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(vested.vested, Uint128::new(750));
+    assert_eq!(vested.claimed, Uint128::new(375));
+    assert_eq!(vested.claimable, Uint128::new(375));
+    assert_eq!(vested.locked, Uint128::zero());
+
+    router
+        .execute_contract(user.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let user_balance: Coin = router
+        .wrap()
+        .query_balance(user.clone(), "uproject".to_string())
+        .unwrap();
+    assert_eq!(user_balance.amount, Uint128::new(750));
+}
+
+// Stake receipt tokens in the campaign contract
+fn stake_receipts(
+    router: &mut App,
+    cw20_addr: &Addr,
+    kickstarter_addr: &Addr,
+    staker: &Addr,
+    amount: u128,
+) {
+    let msg = cw20::Cw20ExecuteMsg::Send {
+        contract: kickstarter_addr.to_string(),
+        amount: Uint128::new(amount),
+        msg: cosmwasm_std::to_json_binary(&crate::msg::ReceiptMsg::Stake {}).unwrap(),
+    };
+    router
+        .execute_contract(staker.clone(), cw20_addr.clone(), &msg, &[])
+        .unwrap();
+}
+
+#[test]
+pub fn try_share_revenue() {
+    let (mut router, cw20_addr, kickstarter_addr, admin, user) = setup_finalized_campaign();
+
+    // Revenue cannot be deposited while nothing is staked
+    let msg = crate::contract::sv::ExecMsg::DepositRevenue {};
+    let res = router.execute_contract(
+        admin.clone(),
+        kickstarter_addr.clone(),
+        &msg,
+        &[coin(400, "ustars".to_string())],
+    );
+    assert!(res.is_err());
+
+    // Both backers stake their receipts
+    stake_receipts(&mut router, &cw20_addr, &kickstarter_addr, &user, 300);
+    stake_receipts(&mut router, &cw20_addr, &kickstarter_addr, &admin, 100);
+    assert_eq!(cw20_balance(&router, &cw20_addr, &user), Uint128::zero());
+
+    // Deposit revenue
+    router
+        .execute_contract(
+            admin.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(400, "ustars".to_string())],
+        )
+        .unwrap();
+
+    let pending: Uint128 = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::PendingRevenue {
+                address: user.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(pending, Uint128::new(300));
+
+    // The user claims their share of the revenue
+    let msg = crate::contract::sv::ExecMsg::ClaimRevenue {};
+    router
+        .execute_contract(user.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let user_balance: Coin = router
+        .wrap()
+        .query_balance(user.clone(), "ustars".to_string())
+        .unwrap();
+    assert_eq!(user_balance.amount, Uint128::new(1000));
+
+    // Revenue cannot be claimed twice
+    let res = router.execute_contract(user.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    // Unstaked receipts no longer earn revenue
+    let msg = crate::contract::sv::ExecMsg::Unstake {
+        amount: Uint128::new(300),
+    };
+    router
+        .execute_contract(user.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+    assert_eq!(cw20_balance(&router, &cw20_addr, &user), Uint128::zero());
+
+    let msg = crate::contract::sv::ExecMsg::DepositRevenue {};
+    router
+        .execute_contract(
+            admin.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(100, "ustars".to_string())],
+        )
+        .unwrap();
+
+    let pending: Uint128 = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::PendingRevenue {
+                address: user.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(pending, Uint128::zero());
+
+    let pending: Uint128 = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::PendingRevenue {
+                address: admin.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(pending, Uint128::new(200));
+}
+
+#[test]
+pub fn try_deposit_revenue_before_finalization() {
+    let (mut router, cw20_addr, kickstarter_addr, admin, user, _) = setup_contracts();
+
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(100, "ustars".to_string())],
+        )
+        .unwrap();
+    stake_receipts(&mut router, &cw20_addr, &kickstarter_addr, &user, 100);
+
+    let msg = crate::contract::sv::ExecMsg::DepositRevenue {};
+    let res = router.execute_contract(
+        admin.clone(),
+        kickstarter_addr.clone(),
+        &msg,
+        &[coin(100, "ustars".to_string())],
+    );
+    assert!(res.is_err());
+}
+
+#[test]
+pub fn try_unbond_staked_receipts() {
+    let (mut router, cw20_addr, kickstarter_addr, _, user) = setup_finalized_campaign();
+
+    stake_receipts(&mut router, &cw20_addr, &kickstarter_addr, &user, 200);
+
+    let query = crate::contract::sv::QueryMsg::Staker {
+        address: user.to_string(),
+    };
+    let staker: StakerResponse = router
+        .wrap()
+        .query_wasm_smart(kickstarter_addr.clone(), &query)
+        .unwrap();
+    assert_eq!(staker.staked, Uint128::new(200));
+    assert_eq!(staker.voting_power, Uint128::new(200));
+
+    // Voting power grows while the receipts stay staked
+    add_block_time(&mut router, crate::contract::VOTING_POWER_MATURITY / 2);
+    let staker: StakerResponse = router
+        .wrap()
+        .query_wasm_smart(kickstarter_addr.clone(), &query)
+        .unwrap();
+    assert_eq!(staker.voting_power, Uint128::new(300));
+
+    // Newly staked receipts start without a boost
+    stake_receipts(&mut router, &cw20_addr, &kickstarter_addr, &user, 100);
+    let staker: StakerResponse = router
+        .wrap()
+        .query_wasm_smart(kickstarter_addr.clone(), &query)
+        .unwrap();
+    assert_eq!(staker.staked, Uint128::new(300));
+    assert_eq!(staker.voting_power, Uint128::new(400));
+
+    add_block_time(&mut router, crate::contract::VOTING_POWER_MATURITY);
+    let staker: StakerResponse = router
+        .wrap()
+        .query_wasm_smart(kickstarter_addr.clone(), &query)
+        .unwrap();
+    assert_eq!(staker.voting_power, Uint128::new(600));
+
+    // Unstaked receipts are held until the unbonding period is over
+    let msg = crate::contract::sv::ExecMsg::Unstake {
+        amount: Uint128::new(100),
+    };
+    router
+        .execute_contract(user.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let staker: StakerResponse = router
+        .wrap()
+        .query_wasm_smart(kickstarter_addr.clone(), &query)
+        .unwrap();
+    assert_eq!(staker.staked, Uint128::new(200));
+    assert_eq!(staker.voting_power, Uint128::new(400));
+    assert_eq!(staker.unbonding.len(), 1);
+    assert_eq!(staker.unbonding[0].amount, Uint128::new(100));
+
+    let msg = crate::contract::sv::ExecMsg::ClaimUnbonded {};
+    let res = router.execute_contract(user.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    add_block_time(&mut router, crate::contract::DEFAULT_UNBONDING_PERIOD);
+    router
+        .execute_contract(user.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+    assert_eq!(cw20_balance(&router, &cw20_addr, &user), Uint128::new(100));
+
+    let staker: StakerResponse = router
+        .wrap()
+        .query_wasm_smart(kickstarter_addr.clone(), &query)
+        .unwrap();
+    assert!(staker.unbonding.is_empty());
+
+    // Unbonded receipts cannot be claimed twice
+    let res = router.execute_contract(user.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+}
+
+#[test]
+#[should_panic(expected = "Oversubscription requires a hard cap")]
+pub fn try_instantiate_oversubscription_without_hard_cap() {
+    setup_contracts_with(crate::storage::CampaignMeta {
+        oversubscription: true,
+        ..default_campaign()
+    });
+}
+
+#[test]
+pub fn try_oversubscribed_campaign() {
+    let (mut router, cw20_addr, kickstarter_addr, admin, user, _) =
+        setup_contracts_with(crate::storage::CampaignMeta {
+            goal: Uint128::new(500),
+            max_raise: Some(Uint128::new(500)),
+            oversubscription: true,
+            ..default_campaign()
+        });
+
+    // Contributions beyond the hard cap are accepted, without receipts
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(600, "ustars".to_string())],
+        )
+        .unwrap();
+    router
+        .execute_contract(
+            admin.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(400, "ustars".to_string())],
+        )
+        .unwrap();
+    assert_eq!(cw20_balance(&router, &cw20_addr, &user), Uint128::zero());
+
+    // The excess can only be claimed once the campaign has ended
+    let msg = crate::contract::sv::ExecMsg::ClaimExcess {};
+    let res = router.execute_contract(user.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    add_block_time(&mut router, 86400);
+
+    // The creator only receives the hard cap
+    let msg = crate::contract::sv::ExecMsg::EndCampaign {};
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let admin_balance: Coin = router
+        .wrap()
+        .query_balance(admin.clone(), "ustars".to_string())
+        .unwrap();
+    assert_eq!(admin_balance.amount, Uint128::new(600 + 475));
+
+    // Half of every contribution is allocated, the rest is refunded
+    let msg = crate::contract::sv::ExecMsg::ClaimExcess {};
+    router
+        .execute_contract(user.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let user_balance: Coin = router
+        .wrap()
+        .query_balance(user.clone(), "ustars".to_string())
+        .unwrap();
+    assert_eq!(user_balance.amount, Uint128::new(700));
+    assert_eq!(cw20_balance(&router, &cw20_addr, &user), Uint128::new(300));
+    assert_eq!(cw20_balance(&router, &cw20_addr, &admin), Uint128::new(200));
+
+    let contract_balance: Coin = router
+        .wrap()
+        .query_balance(kickstarter_addr.clone(), "ustars".to_string())
+        .unwrap();
+    assert_eq!(contract_balance.amount, Uint128::zero());
+
+    let contribution: Option<ContributionResponse> = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Contribution {
+                address: user.to_string(),
+            },
+        )
+        .unwrap();
+    let contribution = contribution.unwrap();
+    assert_eq!(contribution.receipts, Uint128::new(300));
+    assert_eq!(contribution.tier, Some("Bronze".to_string()));
+
+    // The excess cannot be claimed twice
+    let res = router.execute_contract(user.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+}
+
+#[test]
+pub fn try_claim_excess_before_ending_campaign() {
+    let (mut router, _, kickstarter_addr, admin, user, _) =
+        setup_contracts_with(crate::storage::CampaignMeta {
+            goal: Uint128::new(500),
+            max_raise: Some(Uint128::new(500)),
+            oversubscription: true,
+            ..default_campaign()
+        });
+
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(600, "ustars".to_string())],
+        )
+        .unwrap();
+    router
+        .execute_contract(
+            admin.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(400, "ustars".to_string())],
+        )
+        .unwrap();
+
+    add_block_time(&mut router, 86400);
+
+    // The user settles before the campaign is ended
+    let msg = crate::contract::sv::ExecMsg::ClaimExcess {};
+    router
+        .execute_contract(user.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    // The creator still receives the full hard cap minus the fee
+    let msg = crate::contract::sv::ExecMsg::EndCampaign {};
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let admin_balance: Coin = router
+        .wrap()
+        .query_balance(admin.clone(), "ustars".to_string())
+        .unwrap();
+    assert_eq!(admin_balance.amount, Uint128::new(600 + 475));
+
+    // The excess refund is recorded in the user's history
+    let history: Vec<LedgerEntry> = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::HistoryByAddress {
+                address: user.to_string(),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[1].kind, LedgerKind::Refund);
+    assert_eq!(history[1].amount, Uint128::new(300));
+
+    // And the remaining excess is left for the other backer
+    let msg = crate::contract::sv::ExecMsg::ClaimExcess {};
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let contract_balance: Coin = router
+        .wrap()
+        .query_balance(kickstarter_addr.clone(), "ustars".to_string())
+        .unwrap();
+    assert!(contract_balance.amount.is_zero());
+}
+
+#[test]
+pub fn try_end_campaign_after_most_excess_claimed() {
+    let (mut router, _, kickstarter_addr, admin, user, _) =
+        setup_contracts_with(crate::storage::CampaignMeta {
+            goal: Uint128::new(100),
+            max_raise: Some(Uint128::new(100)),
+            oversubscription: true,
+            ..default_campaign()
+        });
+
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(900, "ustars".to_string())],
+        )
+        .unwrap();
+    router
+        .execute_contract(
+            admin.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(100, "ustars".to_string())],
+        )
+        .unwrap();
+
+    add_block_time(&mut router, 86400);
+
+    let msg = crate::contract::sv::ExecMsg::ClaimExcess {};
+    router
+        .execute_contract(user.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    // Ending the campaign does not count the claimed excess twice
+    let msg = crate::contract::sv::ExecMsg::EndCampaign {};
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let admin_balance: Coin = router
+        .wrap()
+        .query_balance(admin.clone(), "ustars".to_string())
+        .unwrap();
+    assert_eq!(admin_balance.amount, Uint128::new(900 + 95));
+}
+
+#[test]
+pub fn try_claim_excess_when_undersubscribed() {
+    let (mut router, cw20_addr, kickstarter_addr, _, user, _) =
+        setup_contracts_with(crate::storage::CampaignMeta {
+            goal: Uint128::new(500),
+            max_raise: Some(Uint128::new(500)),
+            oversubscription: true,
+            ..default_campaign()
+        });
+
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(300, "ustars".to_string())],
+        )
+        .unwrap();
+
+    add_block_time(&mut router, 86400);
+
+    // Without oversubscription, the full contribution is allocated
+    let msg = crate::contract::sv::ExecMsg::ClaimExcess {};
+    router
+        .execute_contract(user.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let user_balance: Coin = router
+        .wrap()
+        .query_balance(user.clone(), "ustars".to_string())
+        .unwrap();
+    assert_eq!(user_balance.amount, Uint128::new(700));
+    assert_eq!(cw20_balance(&router, &cw20_addr, &user), Uint128::new(300));
+}
+
+#[test]
+pub fn try_migrate() {
+    let (mut router, _, kickstarter_addr, admin, _, _) = setup_contracts();
+
+    let version = cw2::query_contract_info(&router.wrap(), kickstarter_addr.clone()).unwrap();
+    assert_eq!(version.contract, "crates.io:fund");
+    assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+
+    let code_id = router.store_code(contract_kickstarter());
+    router
+        .migrate_contract(
+            admin.clone(),
+            kickstarter_addr.clone(),
+            &crate::contract::sv::MigrateMsg {},
+            code_id,
+        )
+        .unwrap();
+
+    // Downgrades are refused
+    {
+        let mut storage = router.contract_storage_mut(&kickstarter_addr);
+        cw2::set_contract_version(storage.as_mut(), "crates.io:fund", "99.0.0").unwrap();
+    }
+    let res = router.migrate_contract(
+        admin.clone(),
+        kickstarter_addr.clone(),
+        &crate::contract::sv::MigrateMsg {},
+        code_id,
+    );
+    assert!(res.is_err());
+}
+
+#[test]
+pub fn try_migrate_legacy_storage() {
+    let (mut router, _, kickstarter_addr, admin, user, _) = setup_contracts();
+
+    // Rewrite the storage as the first release laid it out, keeping only
+    // the receipt token, the denom, the campaign and the contributions
+    {
+        let mut storage = router.contract_storage_mut(&kickstarter_addr);
+        let keys: Vec<Vec<u8>> = storage
+            .range(None, None, cosmwasm_std::Order::Ascending)
+            .map(|(key, _)| key)
+            .collect();
+        for key in keys {
+            if key != b"cw20_address" && key != b"denom" {
+                storage.remove(&key);
+            }
+        }
+
+        cw_storage_plus::Item::new("campaign")
+            .save(
+                storage.as_mut(),
+                &crate::storage::LegacyCampaign {
+                    name: "My Campaign".to_string(),
+                    description: "My Campaign Description".to_string(),
+                    end_time: Timestamp::from_seconds(86400),
+                    goal: Uint128::new(10000),
+                    links: vec![],
+                    tiers: default_campaign().tiers,
+                    creator: admin.clone(),
+                    minimum_contribution: Some(Uint128::new(100)),
+                },
+            )
+            .unwrap();
+        cw_storage_plus::Map::<Addr, Uint128>::new("contributions")
+            .save(storage.as_mut(), user.clone(), &Uint128::new(300))
+            .unwrap();
+    }
+    mint_native(&mut router, &kickstarter_addr, 300, "ustars");
+
+    let code_id = router.store_code(contract_kickstarter());
+    router
+        .migrate_contract(
+            admin.clone(),
+            kickstarter_addr.clone(),
+            &crate::contract::sv::MigrateMsg {},
+            code_id,
+        )
+        .unwrap();
+
+    let version = cw2::query_contract_info(&router.wrap(), kickstarter_addr.clone()).unwrap();
+    assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+
+    // The keys added since the first release are written by the migration
+    let largest_contribution: Uint128 = cw_storage_plus::Item::new("largest_contribution")
+        .load(router.contract_storage(&kickstarter_addr).as_ref())
+        .unwrap();
+    assert_eq!(largest_contribution, Uint128::new(300));
+
+    let config: crate::msg::ConfigResponse = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Config {},
+        )
+        .unwrap();
+    assert_eq!(
+        config.platform_admin,
+        Addr::unchecked(crate::contract::PLATFORM_ADMIN)
+    );
+
+    let history: Vec<crate::storage::CampaignRevision> = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::CampaignHistory {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].version, 0);
+    assert_eq!(history[0].campaign.creator, admin);
+
+    let contribution: Option<ContributionResponse> = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Contribution {
+                address: user.to_string(),
+            },
+        )
+        .unwrap();
+    let contribution = contribution.unwrap();
+    assert_eq!(contribution.amount, Uint128::new(300));
+    assert_eq!(contribution.receipts, Uint128::new(300));
+    assert_eq!(contribution.tier, Some("Bronze".to_string()));
+
+    // Records not written since the migration are still listed
+    let contributions: Vec<ContributionResponse> = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Contributions {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(contributions.len(), 1);
+    assert_eq!(contributions[0].amount, Uint128::new(300));
+
+    // The migrated campaign keeps accepting contributions
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(200, "ustars".to_string())],
+        )
+        .unwrap();
+
+    let contribution: Option<ContributionResponse> = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Contribution {
+                address: user.to_string(),
+            },
+        )
+        .unwrap();
+    let contribution = contribution.unwrap();
+    assert_eq!(contribution.amount, Uint128::new(500));
+    assert_eq!(contribution.tier, Some("Silver".to_string()));
+
+    // The creator receives both the legacy and the new contributions
+    add_block_time(&mut router, 86400);
+    let msg = crate::contract::sv::ExecMsg::EndCampaign {};
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let admin_balance: Coin = router
+        .wrap()
+        .query_balance(admin.clone(), "ustars".to_string())
+        .unwrap();
+    assert_eq!(admin_balance.amount, Uint128::new(1000 + 475));
+}
+
+#[test]
+pub fn try_migrate_legacy_ended_campaign() {
+    let (mut router, _, kickstarter_addr, admin, _, _) = setup_contracts();
+
+    // The first release cleared contributions when the campaign was ended
+    add_block_time(&mut router, 86400);
+    {
+        let mut storage = router.contract_storage_mut(&kickstarter_addr);
+        storage.remove(b"contract_info");
+
+        cw_storage_plus::Item::new("campaign")
+            .save(
+                storage.as_mut(),
+                &crate::storage::LegacyCampaign {
+                    name: "My Campaign".to_string(),
+                    description: "My Campaign Description".to_string(),
+                    end_time: Timestamp::from_seconds(86400),
+                    goal: Uint128::new(10000),
+                    links: vec![],
+                    tiers: default_campaign().tiers,
+                    creator: admin.clone(),
+                    minimum_contribution: Some(Uint128::new(100)),
+                },
+            )
+            .unwrap();
+    }
+
+    let code_id = router.store_code(contract_kickstarter());
+    router
+        .migrate_contract(
+            admin.clone(),
+            kickstarter_addr.clone(),
+            &crate::contract::sv::MigrateMsg {},
+            code_id,
+        )
+        .unwrap();
+
+    let campaign: crate::storage::Campaign = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Info {},
+        )
+        .unwrap();
+    assert!(campaign.finalized);
+
+    // So it cannot be ended a second time
+    let msg = crate::contract::sv::ExecMsg::EndCampaign {};
+    let err = router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Generic error: Campaign has already been finalized"
+    );
+}
+
+#[test]
+pub fn try_transfer_creator() {
+    let (mut router, _, kickstarter_addr, admin, user, _) = setup_contracts();
+    let multisig = router.api().addr_make("multisig");
+
+    // Only the creator can propose a new creator
+    let msg = crate::contract::sv::ExecMsg::ProposeNewCreator {
+        address: multisig.to_string(),
+        expires_at: None,
+    };
+    let res = router.execute_contract(user.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let campaign: crate::storage::Campaign = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Info {},
+        )
+        .unwrap();
+    assert_eq!(campaign.creator, admin);
+    assert_eq!(campaign.pending_creator.unwrap().address, multisig);
+
+    // Only the proposed address can accept
+    let msg = crate::contract::sv::ExecMsg::AcceptCreator {};
+    let res = router.execute_contract(user.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    router
+        .execute_contract(multisig.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let campaign: crate::storage::Campaign = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Info {},
+        )
+        .unwrap();
+    assert_eq!(campaign.creator, multisig);
+    assert_eq!(campaign.pending_creator, None);
+
+    // The previous creator can no longer end the campaign
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(100, "ustars".to_string())],
+        )
+        .unwrap();
+
+    add_block_time(&mut router, 86400);
+    let msg = crate::contract::sv::ExecMsg::EndCampaign {};
+    let res = router.execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    router
+        .execute_contract(multisig.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+}
+
+#[test]
+pub fn try_cancel_creator_transfer() {
+    let (mut router, _, kickstarter_addr, admin, user, _) = setup_contracts();
+
+    let msg = crate::contract::sv::ExecMsg::ProposeNewCreator {
+        address: user.to_string(),
+        expires_at: None,
+    };
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    // Only the creator can cancel the transfer
+    let msg = crate::contract::sv::ExecMsg::CancelTransfer {};
+    let res = router.execute_contract(user.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let msg = crate::contract::sv::ExecMsg::AcceptCreator {};
+    let res = router.execute_contract(user.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+}
+
+#[test]
+pub fn try_accept_expired_creator_transfer() {
+    let (mut router, _, kickstarter_addr, admin, user, _) = setup_contracts();
+
+    // Expiry must be in the future
+    let msg = crate::contract::sv::ExecMsg::ProposeNewCreator {
+        address: user.to_string(),
+        expires_at: Some(Timestamp::from_seconds(1)),
+    };
+    let res = router.execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    let msg = crate::contract::sv::ExecMsg::ProposeNewCreator {
+        address: user.to_string(),
+        expires_at: Some(Timestamp::from_seconds(3600)),
+    };
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    add_block_time(&mut router, 3600);
+
+    let msg = crate::contract::sv::ExecMsg::AcceptCreator {};
+    let res = router.execute_contract(user.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+}
+
+#[test]
+pub fn try_end_campaign_with_beneficiaries() {
+    let (mut router, admin, user, _) = setup_app();
+    let dev = router.api().addr_make("dev");
+    let art = router.api().addr_make("art");
+    let marketing = router.api().addr_make("marketing");
+
+    let beneficiary = |address: &Addr, share_bps: u16| crate::storage::BeneficiaryMeta {
+        address: address.to_string(),
+        share_bps,
+    };
+    let (_, kickstarter_addr) = setup_kickstarter(
+        &mut router,
+        &admin,
+        crate::storage::CampaignMeta {
+            beneficiaries: vec![
+                beneficiary(&dev, 6000),
+                beneficiary(&art, 3000),
+                beneficiary(&marketing, 1000),
+            ],
+            dust_recipient: Some(marketing.to_string()),
+            ..default_campaign()
+        },
+        None,
+    );
+
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(333, "ustars".to_string())],
+        )
+        .unwrap();
+
+    add_block_time(&mut router, 86400);
+
+    let msg = crate::contract::sv::ExecMsg::EndCampaign {};
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    // 317 are paid out after the fee, and the rounding dust goes to marketing
+    let balance = |router: &App, address: &Addr| {
+        router
+            .wrap()
+            .query_balance(address.clone(), "ustars".to_string())
+            .unwrap()
+            .amount
+    };
+    assert_eq!(balance(&router, &dev), Uint128::new(190));
+    assert_eq!(balance(&router, &art), Uint128::new(95));
+    assert_eq!(balance(&router, &marketing), Uint128::new(32));
+    assert_eq!(balance(&router, &admin), Uint128::new(1000));
+    assert_eq!(balance(&router, &kickstarter_addr), Uint128::zero());
+}
+
+#[test]
+#[should_panic(expected = "Beneficiary shares must sum to 10000 basis points")]
+pub fn try_instantiate_with_invalid_beneficiary_shares() {
+    let (mut router, admin, user, _) = setup_app();
+
+    setup_kickstarter(
+        &mut router,
+        &admin,
+        crate::storage::CampaignMeta {
+            beneficiaries: vec![
+                crate::storage::BeneficiaryMeta {
+                    address: admin.to_string(),
+                    share_bps: 6000,
+                },
+                crate::storage::BeneficiaryMeta {
+                    address: user.to_string(),
+                    share_bps: 3000,
+                },
+            ],
+            ..default_campaign()
+        },
+        None,
+    );
+}
+
+// Set up a treasury contract to receive payouts
+fn setup_treasury(router: &mut App, admin: &Addr) -> Addr {
+    let treasury_id = router.store_code(contract_treasury());
+    router
+        .instantiate_contract(treasury_id, admin.clone(), &Empty {}, &[], "TREASURY", None)
+        .unwrap()
+}
+
+#[test]
+pub fn try_end_campaign_with_payout_address() {
+    let (mut router, admin, user, _) = setup_app();
+    let treasury = setup_treasury(&mut router, &admin);
+
+    let (_, kickstarter_addr) = setup_kickstarter(
+        &mut router,
+        &admin,
+        crate::storage::CampaignMeta {
+            payout_address: Some(treasury.to_string()),
+            payout_callback: Some(
+                cosmwasm_std::to_json_binary(&mock_treasury::ExecuteMsg::Deposit {}).unwrap(),
+            ),
+            ..default_campaign()
+        },
+        None,
+    );
+
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(300, "ustars".to_string())],
+        )
+        .unwrap();
+
+    add_block_time(&mut router, 86400);
+
+    let msg = crate::contract::sv::ExecMsg::EndCampaign {};
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    // The payout lands in the treasury, which is notified of the deposit
+    let treasury_balance: Coin = router
+        .wrap()
+        .query_balance(treasury.clone(), "ustars".to_string())
+        .unwrap();
+    assert_eq!(treasury_balance.amount, Uint128::new(285));
+
+    let deposited: Uint128 = router
+        .wrap()
+        .query_wasm_smart(treasury.clone(), &Empty {})
+        .unwrap();
+    assert_eq!(deposited, Uint128::new(285));
+
+    let admin_balance: Coin = router
+        .wrap()
+        .query_balance(admin.clone(), "ustars".to_string())
+        .unwrap();
+    assert_eq!(admin_balance.amount, Uint128::new(1000));
+}
+
+#[test]
+pub fn try_end_campaign_with_failing_payout_callback() {
+    let (mut router, admin, user, _) = setup_app();
+    let treasury = setup_treasury(&mut router, &admin);
+
+    // The treasury does not understand this callback
+    let (_, kickstarter_addr) = setup_kickstarter(
+        &mut router,
+        &admin,
+        crate::storage::CampaignMeta {
+            payout_address: Some(treasury.to_string()),
+            payout_callback: Some(Binary::from(br#"{"unknown":{}}"#)),
+            ..default_campaign()
+        },
+        None,
+    );
+
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(300, "ustars".to_string())],
+        )
+        .unwrap();
+
+    add_block_time(&mut router, 86400);
+
+    let msg = crate::contract::sv::ExecMsg::EndCampaign {};
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    // The payout still lands in the treasury, without the notification
+    let treasury_balance: Coin = router
+        .wrap()
+        .query_balance(treasury.clone(), "ustars".to_string())
+        .unwrap();
+    assert_eq!(treasury_balance.amount, Uint128::new(285));
+
+    let deposited: Uint128 = router
+        .wrap()
+        .query_wasm_smart(treasury.clone(), &Empty {})
+        .unwrap();
+    assert!(deposited.is_zero());
+
+    let campaign: crate::storage::Campaign = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Info {},
+        )
+        .unwrap();
+    assert!(campaign.finalized);
+}
+
+#[test]
+#[should_panic(expected = "Payout address must be a contract")]
+pub fn try_instantiate_with_wallet_payout_address() {
+    let (mut router, admin, user, _) = setup_app();
+
+    setup_kickstarter(
+        &mut router,
+        &admin,
+        crate::storage::CampaignMeta {
+            payout_address: Some(user.to_string()),
+            ..default_campaign()
+        },
+        None,
+    );
+}
+
+#[test]
+pub fn try_operator_roles() {
+    let (mut router, _, kickstarter_addr, admin, user, _) = setup_contracts();
+    let editor = router.api().addr_make("editor");
+    let finance = router.api().addr_make("finance");
+
+    // Only owners can grant roles
+    let msg = crate::contract::sv::ExecMsg::GrantRole {
+        address: editor.to_string(),
+        role: Role::Editor,
+    };
+    let res = router.execute_contract(user.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let msg = crate::contract::sv::ExecMsg::GrantRole {
+        address: finance.to_string(),
+        role: Role::Finance,
+    };
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let operators: Vec<OperatorResponse> = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Operators {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(operators.len(), 2);
+    assert!(operators.contains(&OperatorResponse {
+        address: editor.clone(),
+        roles: vec![Role::Editor],
+    }));
+
+    // Editors can change the description and links
+    let msg = crate::contract::sv::ExecMsg::UpdateCampaign {
+        description: "New Description".to_string(),
+        links: vec![],
+        minimum_contribution: None,
+        name: None,
+        goal: None,
+        tiers: None,
+        end_time: None,
+    };
+    router
+        .execute_contract(editor.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let res = router.execute_contract(finance.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    // But nothing which affects contributions
+    let msg = crate::contract::sv::ExecMsg::UpdateCampaign {
+        description: "New Description".to_string(),
+        links: vec![],
+        minimum_contribution: Some(Uint128::new(200)),
+        name: None,
+        goal: None,
+        tiers: None,
+        end_time: None,
+    };
+    let res = router.execute_contract(editor.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    // The creator can change the minimum, and a zero minimum removes it
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let msg = crate::contract::sv::ExecMsg::UpdateCampaign {
+        description: "New Description".to_string(),
+        links: vec![],
+        minimum_contribution: Some(Uint128::zero()),
+        name: None,
+        goal: None,
+        tiers: None,
+        end_time: None,
+    };
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let campaign: crate::storage::Campaign = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Info {},
+        )
+        .unwrap();
+    assert_eq!(campaign.minimum_contribution, None);
+
+    // Editors cannot manage roles
+    let msg = crate::contract::sv::ExecMsg::GrantRole {
+        address: user.to_string(),
+        role: Role::Editor,
+    };
+    let res = router.execute_contract(editor.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(100, "ustars".to_string())],
+        )
+        .unwrap();
+
+    add_block_time(&mut router, 86400);
+
+    // Only finance operators can end the campaign, and the payout still
+    // goes to the creator
+    let msg = crate::contract::sv::ExecMsg::EndCampaign {};
+    let res = router.execute_contract(editor.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    router
+        .execute_contract(finance.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let admin_balance: Coin = router
+        .wrap()
+        .query_balance(admin.clone(), "ustars".to_string())
+        .unwrap();
+    assert_eq!(admin_balance.amount, Uint128::new(1095));
+}
+
+#[test]
+pub fn try_revoke_role() {
+    let (mut router, _, kickstarter_addr, admin, user, _) = setup_contracts();
+    let owner = router.api().addr_make("owner");
+
+    let msg = crate::contract::sv::ExecMsg::GrantRole {
+        address: owner.to_string(),
+        role: Role::Owner,
+    };
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    // Owners can manage editors, but not other owners
+    let msg = crate::contract::sv::ExecMsg::GrantRole {
+        address: user.to_string(),
+        role: Role::Editor,
+    };
+    router
+        .execute_contract(owner.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let msg = crate::contract::sv::ExecMsg::GrantRole {
+        address: user.to_string(),
+        role: Role::Owner,
+    };
+    let res = router.execute_contract(owner.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    let msg = crate::contract::sv::ExecMsg::RevokeRole {
+        address: user.to_string(),
+        role: Role::Editor,
+    };
+    router
+        .execute_contract(owner.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    // Revoked editors can no longer edit the campaign
+    let msg = crate::contract::sv::ExecMsg::UpdateCampaign {
+        description: "New Description".to_string(),
+        links: vec![],
+        minimum_contribution: None,
+        name: None,
+        goal: None,
+        tiers: None,
+        end_time: None,
+    };
+    let res = router.execute_contract(user.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    let operators: Vec<OperatorResponse> = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Operators {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        operators,
+        vec![OperatorResponse {
+            address: owner.clone(),
+            roles: vec![Role::Owner],
+        }]
+    );
+}
+
+#[test]
+pub fn try_freeze_campaign() {
+    let (mut router, _, kickstarter_addr, admin, user, _) = setup_contracts();
+    let platform_admin = Addr::unchecked(crate::contract::PLATFORM_ADMIN);
+
+    let config: crate::msg::ConfigResponse = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Config {},
+        )
+        .unwrap();
+    assert_eq!(config.platform_admin, platform_admin);
+
+    let contribute = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &contribute,
+            &[coin(100, "ustars".to_string())],
+        )
+        .unwrap();
+
+    // Only the platform admin can freeze a campaign
+    let msg = crate::contract::sv::ExecMsg::Freeze {
+        reason: "Reported as a scam".to_string(),
+    };
+    let res = router.execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    router
+        .execute_contract(platform_admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    // Frozen campaigns accept no contributions and cannot be paid out
+    let res = router.execute_contract(
+        user.clone(),
+        kickstarter_addr.clone(),
+        &contribute,
+        &[coin(100, "ustars".to_string())],
+    );
+    assert!(res.is_err());
+
+    add_block_time(&mut router, 86400);
+
+    let end = crate::contract::sv::ExecMsg::EndCampaign {};
+    let res = router.execute_contract(admin.clone(), kickstarter_addr.clone(), &end, &[]);
+    assert!(res.is_err());
+
+    let msg = crate::contract::sv::ExecMsg::Unfreeze {
+        reason: "Report dismissed".to_string(),
+    };
+    router
+        .execute_contract(platform_admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &end, &[])
+        .unwrap();
+
+    let log: Vec<ModerationEntry> = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::ModerationLog {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(log.len(), 2);
+    assert_eq!(log[0].action, ModerationAction::Freeze);
+    assert_eq!(log[0].reason, "Reported as a scam");
+    assert_eq!(log[0].admin, platform_admin);
+    assert_eq!(log[1].action, ModerationAction::Unfreeze);
+}
+
+#[test]
+pub fn try_void_campaign() {
+    let (mut router, cw20_addr, kickstarter_addr, admin, user, _) = setup_contracts();
+    let platform_admin = Addr::unchecked(crate::contract::PLATFORM_ADMIN);
+
+    let contribute = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &contribute,
+            &[coin(300, "ustars".to_string())],
+        )
+        .unwrap();
+
+    // Refunds can only be claimed from voided campaigns
+    let claim = crate::contract::sv::ExecMsg::ClaimRefund {};
+    let res = router.execute_contract(user.clone(), kickstarter_addr.clone(), &claim, &[]);
+    assert!(res.is_err());
+
+    let msg = crate::contract::sv::ExecMsg::Void {
+        reason: "Creator disappeared".to_string(),
+    };
+    router
+        .execute_contract(platform_admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let campaign: crate::storage::Campaign = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Info {},
+        )
+        .unwrap();
+    assert_eq!(campaign.status, CampaignStatus::Voided);
+
+    // Voided campaigns cannot be unfrozen or paid out
+    let msg = crate::contract::sv::ExecMsg::Unfreeze {
+        reason: "Mistake".to_string(),
+    };
+    let res = router.execute_contract(platform_admin.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    let res = router.execute_contract(
+        user.clone(),
+        kickstarter_addr.clone(),
+        &contribute,
+        &[coin(100, "ustars".to_string())],
+    );
+    assert!(res.is_err());
+
+    add_block_time(&mut router, 86400);
+
+    let msg = crate::contract::sv::ExecMsg::EndCampaign {};
+    let res = router.execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    // Backers get their full contribution back by returning their receipts,
+    // which are burnt
+    let res = router.execute_contract(user.clone(), kickstarter_addr.clone(), &claim, &[]);
+    assert!(res.is_err());
+
+    let refunded = refund_receipts(&mut router, &cw20_addr, &kickstarter_addr, &user, 300);
+    assert_eq!(refunded, Uint128::new(300));
+    assert_eq!(cw20_balance(&router, &cw20_addr, &user), Uint128::zero());
+
+    let token_info: cw20::TokenInfoResponse = router
+        .wrap()
+        .query_wasm_smart(cw20_addr.clone(), &cw20::Cw20QueryMsg::TokenInfo {})
+        .unwrap();
+    assert!(token_info.total_supply.is_zero());
+
+    let res = router.execute_contract(user.clone(), kickstarter_addr.clone(), &claim, &[]);
+    assert!(res.is_err());
+}
+
+#[test]
+pub fn try_update_platform_admin() {
+    let (mut router, _, kickstarter_addr, admin, _, _) = setup_contracts();
+    let platform_admin = Addr::unchecked(crate::contract::PLATFORM_ADMIN);
+    let new_admin = router.api().addr_make("new_admin");
+
+    // Only the platform admin can hand the role over
+    let msg = crate::contract::sv::ExecMsg::UpdatePlatformAdmin {
+        address: admin.to_string(),
+    };
+    let res = router.execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    let msg = crate::contract::sv::ExecMsg::UpdatePlatformAdmin {
+        address: new_admin.to_string(),
+    };
+    router
+        .execute_contract(platform_admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let config: crate::msg::ConfigResponse = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Config {},
+        )
+        .unwrap();
+    assert_eq!(config.platform_admin, new_admin);
+
+    // The previous admin loses its powers
+    let msg = crate::contract::sv::ExecMsg::Pause {
+        reason: "Investigating a bug".to_string(),
+    };
+    let res = router.execute_contract(platform_admin, kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    router
+        .execute_contract(new_admin, kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+}
+
+#[test]
+pub fn try_pause_contract() {
+    let (mut router, cw20_addr, kickstarter_addr, admin, user, _) = setup_contracts();
+    let platform_admin = Addr::unchecked(crate::contract::PLATFORM_ADMIN);
+
+    let contribute = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &contribute,
+            &[coin(300, "ustars".to_string())],
+        )
+        .unwrap();
+
+    // Only the platform admin can pause the contract
+    let msg = crate::contract::sv::ExecMsg::Pause {
+        reason: "Investigating a bug".to_string(),
+    };
+    let res = router.execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    router
+        .execute_contract(platform_admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let config: crate::msg::ConfigResponse = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Config {},
+        )
+        .unwrap();
+    assert!(config.paused);
+    assert_eq!(config.pause_reason, Some("Investigating a bug".to_string()));
+
+    // Contributions are halted
+    let res = router.execute_contract(
+        user.clone(),
+        kickstarter_addr.clone(),
+        &contribute,
+        &[coin(100, "ustars".to_string())],
+    );
+    assert!(res.is_err());
+
+    // Refunds through the receipts still work
+    let refunded = refund_receipts(&mut router, &cw20_addr, &kickstarter_addr, &user, 100);
+    assert_eq!(refunded, Uint128::new(100));
+
+    // The campaign cannot be ended while paused
+    add_block_time(&mut router, 86400);
+    let end = crate::contract::sv::ExecMsg::EndCampaign {};
+    let res = router.execute_contract(admin.clone(), kickstarter_addr.clone(), &end, &[]);
+    assert!(res.is_err());
+
+    let msg = crate::contract::sv::ExecMsg::Unpause {};
+    let res = router.execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    router
+        .execute_contract(platform_admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let config: crate::msg::ConfigResponse = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Config {},
+        )
+        .unwrap();
+    assert!(!config.paused);
+    assert_eq!(config.pause_reason, None);
+
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &end, &[])
+        .unwrap();
+}
+
+// Mint native tokens straight to an address
+fn mint_native(router: &mut App, address: &Addr, amount: u128, denom: &str) {
+    router
+        .sudo(cw_multi_test::SudoMsg::Bank(
+            cw_multi_test::BankSudo::Mint {
+                to_address: address.to_string(),
+                amount: coins(amount, denom),
+            },
+        ))
+        .unwrap();
+}
+
+#[test]
+pub fn try_recover_tokens() {
+    let (mut router, cw20_addr, kickstarter_addr, admin, user, _) = setup_contracts();
+    let platform_admin = Addr::unchecked(crate::contract::PLATFORM_ADMIN);
+    let recipient = router.api().addr_make("recipient");
+
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(300, "ustars".to_string())],
+        )
+        .unwrap();
+
+    // Other denoms can be recovered in full, by the platform admin only
+    mint_native(&mut router, &kickstarter_addr, 500, "uatom");
+    let msg = crate::contract::sv::ExecMsg::RecoverTokens {
+        token: crate::storage::Token::Native {
+            denom: "uatom".to_string(),
+        },
+        recipient: recipient.to_string(),
+    };
+    let res = router.execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    router
+        .execute_contract(platform_admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+    let balance: Coin = router
+        .wrap()
+        .query_balance(recipient.clone(), "uatom".to_string())
+        .unwrap();
+    assert_eq!(balance.amount, Uint128::new(500));
+
+    // Contributions cannot be recovered, only the surplus above them
+    let msg = crate::contract::sv::ExecMsg::RecoverTokens {
+        token: crate::storage::Token::Native {
+            denom: "ustars".to_string(),
+        },
+        recipient: recipient.to_string(),
+    };
+    let res = router.execute_contract(platform_admin.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    mint_native(&mut router, &kickstarter_addr, 50, "ustars");
+    router
+        .execute_contract(platform_admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+    let balance: Coin = router
+        .wrap()
+        .query_balance(recipient.clone(), "ustars".to_string())
+        .unwrap();
+    assert_eq!(balance.amount, Uint128::new(50));
+    let balance: Coin = router
+        .wrap()
+        .query_balance(kickstarter_addr.clone(), "ustars".to_string())
+        .unwrap();
+    assert_eq!(balance.amount, Uint128::new(300));
+
+    // Receipt tokens cannot be recovered
+    let msg = crate::contract::sv::ExecMsg::RecoverTokens {
+        token: crate::storage::Token::Cw20 {
+            address: cw20_addr.clone(),
+        },
+        recipient: recipient.to_string(),
+    };
+    let res = router.execute_contract(platform_admin.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    // Any other CW20 can
+    let cw20_id = router.store_code(contract_cw20());
+    let msg = cw20_base::msg::InstantiateMsg {
+        name: String::from("Random Token"),
+        symbol: String::from("RND"),
+        decimals: 6,
+        initial_balances: vec![cw20::Cw20Coin {
+            address: user.to_string(),
+            amount: Uint128::new(100),
+        }],
+        mint: None,
+        marketing: None,
+    };
+    let random_addr = router
+        .instantiate_contract(cw20_id, admin.clone(), &msg, &[], "RND_CW20", None)
+        .unwrap();
+
+    let msg = cw20::Cw20ExecuteMsg::Transfer {
+        recipient: kickstarter_addr.to_string(),
+        amount: Uint128::new(100),
+    };
+    router
+        .execute_contract(user.clone(), random_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let msg = crate::contract::sv::ExecMsg::RecoverTokens {
+        token: crate::storage::Token::Cw20 {
+            address: random_addr.clone(),
+        },
+        recipient: user.to_string(),
+    };
+    router
+        .execute_contract(platform_admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+    assert_eq!(
+        cw20_balance(&router, &random_addr, &user),
+        Uint128::new(100)
+    );
+}
+
+#[test]
+pub fn try_recover_tokens_above_distribution() {
+    let (mut router, _, kickstarter_addr, admin, _) = setup_finalized_campaign();
+    let platform_admin = Addr::unchecked(crate::contract::PLATFORM_ADMIN);
+
+    mint_native(&mut router, &admin, 1000, "uproject");
+    let msg = crate::contract::sv::ExecMsg::DepositTokens {
+        claim_deadline: Some(Timestamp::from_seconds(200000)),
+        vesting: None,
+    };
+    router
+        .execute_contract(
+            admin.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(1000, "uproject".to_string())],
+        )
+        .unwrap();
+
+    // Project tokens owed to backers cannot be recovered
+    let msg = crate::contract::sv::ExecMsg::RecoverTokens {
+        token: crate::storage::Token::Native {
+            denom: "uproject".to_string(),
+        },
+        recipient: admin.to_string(),
+    };
+    let res = router.execute_contract(platform_admin.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    mint_native(&mut router, &kickstarter_addr, 200, "uproject");
+    router
+        .execute_contract(platform_admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let balance: Coin = router
+        .wrap()
+        .query_balance(kickstarter_addr.clone(), "uproject".to_string())
+        .unwrap();
+    assert_eq!(balance.amount, Uint128::new(1000));
+}
+
+#[test]
+pub fn try_extend_deadline() {
+    let (mut router, cw20_addr, kickstarter_addr, admin, user, _) = setup_contracts();
+
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(300, "ustars".to_string())],
+        )
+        .unwrap();
+
+    // Only the creator can extend the deadline
+    let msg = crate::contract::sv::ExecMsg::ExtendDeadline {
+        end_time: Timestamp::from_seconds(2 * 86400),
+    };
+    let res = router.execute_contract(user.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    // The one-year limit still applies
+    let too_late = crate::contract::sv::ExecMsg::ExtendDeadline {
+        end_time: Timestamp::from_seconds(2 + crate::contract::MAX_CAMPAIGN_DURATION),
+    };
+    let res = router.execute_contract(admin.clone(), kickstarter_addr.clone(), &too_late, &[]);
+    assert!(res.is_err());
+
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let campaign: crate::storage::Campaign = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::Info {},
+        )
+        .unwrap();
+    assert_eq!(campaign.end_time, Timestamp::from_seconds(2 * 86400));
+    assert_eq!(campaign.deadline_extensions, 1);
+    assert_eq!(
+        campaign.refund_window.clone().unwrap().ends_at,
+        Timestamp::from_seconds(2 * 86400)
+    );
+
+    // The extension is recorded as a new version of the campaign
+    let history: Vec<crate::storage::CampaignRevision> = router
+        .wrap()
+        .query_wasm_smart(
+            kickstarter_addr.clone(),
+            &crate::contract::sv::QueryMsg::CampaignHistory {
+                start_after: Some(0),
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(history.len(), 1);
+    assert!(history[0].changes.contains(&"end_time".to_string()));
+    assert_eq!(history[0].campaign, campaign);
+
+    // The deadline can only be extended once
+    let msg = crate::contract::sv::ExecMsg::ExtendDeadline {
+        end_time: Timestamp::from_seconds(3 * 86400),
+    };
+    let res = router.execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    // Contributions are accepted past the original end time
+    add_block_time(&mut router, 86400);
+    let msg = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            admin.clone(),
+            kickstarter_addr.clone(),
+            &msg,
+            &[coin(100, "ustars".to_string())],
+        )
+        .unwrap();
+
+    // Backers holding receipts refund by returning them
+    let msg = crate::contract::sv::ExecMsg::ClaimRefund {};
+    let res = router.execute_contract(user.clone(), kickstarter_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    let refunded = refund_receipts(&mut router, &cw20_addr, &kickstarter_addr, &user, 300);
+    assert_eq!(refunded, Uint128::new(300));
+}
+
+#[test]
+pub fn try_refund_oversubscribed_after_extension() {
+    let (mut router, _, kickstarter_addr, admin, user, _) =
+        setup_contracts_with(crate::storage::CampaignMeta {
+            goal: Uint128::new(500),
+            max_raise: Some(Uint128::new(500)),
+            oversubscription: true,
+            ..default_campaign()
+        });
+
+    let contribute = crate::contract::sv::ExecMsg::Contribute {
+        memo: None,
+        allowlist: None,
+    };
+    router
+        .execute_contract(
+            user.clone(),
+            kickstarter_addr.clone(),
+            &contribute,
+            &[coin(600, "ustars".to_string())],
+        )
+        .unwrap();
+
+    // Oversubscribed campaigns cannot be refunded without an extension
+    let claim = crate::contract::sv::ExecMsg::ClaimRefund {};
+    let res = router.execute_contract(user.clone(), kickstarter_addr.clone(), &claim, &[]);
+    assert!(res.is_err());
+
+    let msg = crate::contract::sv::ExecMsg::ExtendDeadline {
+        end_time: Timestamp::from_seconds(30 * 86400),
+    };
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    // Backers who joined after the extension are not covered
+    add_block_time(&mut router, 60);
+    router
+        .execute_contract(
+            admin.clone(),
+            kickstarter_addr.clone(),
+            &contribute,
+            &[coin(400, "ustars".to_string())],
+        )
+        .unwrap();
+    let res = router.execute_contract(admin.clone(), kickstarter_addr.clone(), &claim, &[]);
+    assert!(res.is_err());
+
+    router
+        .execute_contract(user.clone(), kickstarter_addr.clone(), &claim, &[])
+        .unwrap();
+
+    let user_balance: Coin = router
+        .wrap()
+        .query_balance(user.clone(), "ustars".to_string())
+        .unwrap();
+    assert_eq!(user_balance.amount, Uint128::new(1000));
+
+    // The remaining backer is allocated in full once the campaign ends
+    add_block_time(&mut router, 30 * 86400);
+    let msg = crate::contract::sv::ExecMsg::ClaimExcess {};
+    router
+        .execute_contract(admin.clone(), kickstarter_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let admin_balance: Coin = router
+        .wrap()
+        .query_balance(admin.clone(), "ustars".to_string())
+        .unwrap();
+    assert_eq!(admin_balance.amount, Uint128::new(600));
+}